@@ -0,0 +1,196 @@
+use crate::core::error::GdemError;
+use crate::core::tags::{Architecture, Language, OS, Tag};
+use serde::{Deserialize, Serialize};
+use std::fs;
+use std::path::Path;
+
+/// releases.json 中的一个发布版本
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Release {
+    pub tag_name: String,
+    #[serde(default)]
+    pub name: String,
+    /// 发布说明
+    #[serde(default)]
+    pub body: String,
+    #[serde(default)]
+    pub published_at: String,
+    #[serde(default)]
+    pub assets: Vec<Asset>,
+}
+
+/// 发布版本中的一个文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Asset {
+    pub name: String,
+    #[serde(default)]
+    pub size: u64,
+    #[serde(default)]
+    pub updated_at: String,
+    #[serde(default)]
+    pub browser_download_url: String,
+}
+
+/// 文件的类型
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AssetKind {
+    Editor,
+    Templates,
+    Aar,
+    Checksum,
+    Other,
+}
+
+impl AssetKind {
+    pub fn get_labels(&self) -> &'static str {
+        match self {
+            Self::Editor => "editor",
+            Self::Templates => "templates",
+            Self::Aar => "AAR",
+            Self::Checksum => "checksum",
+            Self::Other => "other",
+        }
+    }
+}
+
+impl Asset {
+    pub fn kind(&self) -> AssetKind {
+        let name = self.name.as_str();
+        if name.contains("SUMS") {
+            AssetKind::Checksum
+        } else if Language::ExportTemplate.tag_in(name) {
+            AssetKind::Templates
+        } else if name.ends_with(".aar") {
+            AssetKind::Aar
+        } else if name.ends_with(".zip") {
+            AssetKind::Editor
+        } else {
+            AssetKind::Other
+        }
+    }
+
+    /// 是否为 C# (mono) 版本
+    pub fn is_mono(&self) -> bool {
+        Language::CSharp.tag_in(&self.name)
+    }
+
+    pub fn get_flavor(&self) -> &'static str {
+        if self.is_mono() { "mono" } else { "standard" }
+    }
+
+    /// 编辑器的系统，模板等通用文件返回 None
+    pub fn os(&self) -> Option<OS> {
+        if self.kind() != AssetKind::Editor {
+            return None;
+        }
+        OS::from_keyword(&self.name)
+    }
+
+    pub fn arch(&self) -> Option<Architecture> {
+        if self.kind() != AssetKind::Editor {
+            return None;
+        }
+        Architecture::from_keyword(&self.name)
+    }
+
+    /// 系统和架构的标签，如 `Linux/AMD64`，通用文件为 `any`
+    pub fn get_platform(&self) -> String {
+        match (self.os(), self.arch()) {
+            (Some(os), Some(arch)) => format!("{}/{}", os.get_labels(), arch.get_labels()),
+            (Some(os), None) => os.get_labels().to_string(),
+            _ => "any".to_string(),
+        }
+    }
+
+    /// 是否可以在本机使用，只有编辑器区分系统和架构
+    pub fn is_local_platform(&self) -> bool {
+        if self.kind() != AssetKind::Editor {
+            return true;
+        }
+        OS::get_local_os().tag_in(&self.name) && Architecture::get_local_arch().tag_in(&self.name)
+    }
+
+    /// 更新日期，如 `2025-03-01`
+    pub fn get_date(&self) -> &str {
+        self.updated_at.split('T').next().unwrap_or_default()
+    }
+}
+
+impl Release {
+    /// 数字版本号，如 `4.4-stable` -> `(4, 4, 0)`
+    pub fn get_version(&self) -> Option<(u64, u64, u64)> {
+        parse_version(&self.tag_name)
+    }
+
+    /// 发布日期，如 `2025-03-26`
+    pub fn get_date(&self) -> &str {
+        self.published_at.split('T').next().unwrap_or_default()
+    }
+
+    /// 发布通道，如 `stable`、`rc1`
+    pub fn get_channel(&self) -> &str {
+        self.tag_name.split_once('-').map(|(_, c)| c).unwrap_or("stable")
+    }
+}
+
+/// 解析版本号，缺省的次版本号和修订号记为 0
+///
+/// # Examples
+///
+/// ```
+/// use gdem::core::catalog::parse_version;
+/// assert_eq!(parse_version("4.4.1-stable"), Some((4, 4, 1)));
+/// assert_eq!(parse_version("3.x"), Some((3, 0, 0)));
+/// assert_eq!(parse_version("stable"), None);
+/// ```
+pub fn parse_version(version: &str) -> Option<(u64, u64, u64)> {
+    let numbers = version.split('-').next()?;
+    let mut parts = numbers.split('.').map(|p| p.parse::<u64>().ok());
+    let major = parts.next()??;
+    let minor = parts.next().flatten().unwrap_or(0);
+    let patch = parts.next().flatten().unwrap_or(0);
+    Some((major, minor, patch))
+}
+
+/// 从 data/releases.json 加载发布版本目录
+pub fn load_catalog(data: &Path) -> Result<Vec<Release>, GdemError> {
+    let file_path = data.join("releases.json");
+    if !file_path.exists() {
+        return Err(GdemError::NotFound(
+            "releases.json not found, please run `gdem sync` first".to_string(),
+        ));
+    }
+    let text = fs::read_to_string(&file_path)?;
+    let releases: Vec<Release> = serde_json::from_str(&text)?;
+    Ok(releases)
+}
+
+/// 查找发布版本
+///
+/// 完整标签（如 `4.4-stable`）精确匹配，`4.4` 优先匹配版本号相同的发布，否则返回第一个以 version 开头的版本
+pub fn find_release<'a>(releases: &'a [Release], version: &str) -> Option<&'a Release> {
+    releases
+        .iter()
+        .find(|r| r.tag_name == version)
+        .or_else(|| {
+            releases
+                .iter()
+                .find(|r| r.tag_name.split('-').next() == Some(version))
+        })
+        .or_else(|| releases.iter().find(|r| r.tag_name.starts_with(version)))
+}
+
+/// 发布版本的排序键，同一版本号下 dev < alpha < beta < rc < stable
+pub fn release_key(release: &Release) -> (u64, u64, u64, u8, u64) {
+    let (major, minor, patch) = release.get_version().unwrap_or_default();
+    let channel = release.get_channel();
+    let rank = ["dev", "alpha", "beta", "rc", "stable"]
+        .iter()
+        .position(|c| channel.starts_with(c))
+        .unwrap_or(0) as u8;
+    let number = channel
+        .trim_start_matches(|c: char| c.is_ascii_alphabetic())
+        .parse::<u64>()
+        .unwrap_or(0);
+    (major, minor, patch, rank, number)
+}
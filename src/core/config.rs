@@ -1,43 +1,46 @@
+use crate::core::error::GdemError;
 use serde_json::Value;
 use std::fs::create_dir_all;
 use std::path::{Path, PathBuf};
 
 pub trait ConfigTrait {
     // abstract
-    fn get_root() -> PathBuf;
+    fn get_root() -> Result<PathBuf, GdemError>;
     fn new(root: PathBuf) -> Self;
-    fn load(root: PathBuf) -> Self;
-    fn init() -> Self;
-    fn save(&self);
-    fn init_path(&self);
+    fn load(root: PathBuf) -> Result<Self, GdemError>
+    where
+        Self: Sized;
+    fn init() -> Result<Self, GdemError>
+    where
+        Self: Sized;
+    fn save(&self) -> Result<(), GdemError>;
+    fn init_path(&self) -> Result<(), GdemError>;
     // mut
     fn switch_version(&mut self, version: &str);
 
     // impl
-    fn get_config_path() -> PathBuf {
-        Self::get_root().join("config.json")
+    fn get_config_path() -> Result<PathBuf, GdemError> {
+        Ok(Self::get_root()?.join("config.json"))
     }
-    fn init_dir(dirs: &[&Path]) {
+    fn init_dir(dirs: &[&Path]) -> Result<(), GdemError> {
         for dir in dirs {
             if !dir.exists() {
-                create_dir_all(dir).unwrap();
+                create_dir_all(dir)?;
             }
         }
+        Ok(())
     }
+    // 缺失的字段使用默认值，以兼容旧版本的配置文件
     fn val2path(val: Option<&Value>) -> PathBuf {
-        val.unwrap().as_str().unwrap_or_default().into()
+        val.and_then(|v| v.as_str()).unwrap_or_default().into()
     }
     fn val2str(val: Option<&Value>) -> String {
-        val.unwrap().as_str().unwrap_or_default().to_string()
+        val.and_then(|v| v.as_str()).unwrap_or_default().to_string()
     }
     fn val2bool(val: Option<&Value>) -> bool {
-        val.unwrap().as_bool().unwrap_or_default()
+        val.and_then(|v| v.as_bool()).unwrap_or_default()
     }
     fn val2num(val: Option<&Value>) -> f64 {
-        val.unwrap()
-            .as_number()
-            .unwrap()
-            .as_f64()
-            .unwrap_or_default()
+        val.and_then(|v| v.as_f64()).unwrap_or_default()
     }
 }
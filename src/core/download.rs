@@ -0,0 +1,822 @@
+use crate::core::error::GdemError;
+use crate::core::hash::Sha512State;
+use crate::core::style::new_multi_progress;
+use crate::core::utils::{
+    client_builder, load_json, partial_path, save_json, sha512sum, state_path,
+};
+use futures_util::future::try_join_all;
+use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use reqwest::header::{CONTENT_LENGTH, CONTENT_RANGE, ETAG, HeaderMap, IF_RANGE, RANGE};
+use reqwest::{Client, RequestBuilder, Response, StatusCode};
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::io::SeekFrom;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+use tokio::fs::{File as TokioFile, create_dir_all, remove_file, rename};
+use tokio::io::{AsyncReadExt, AsyncSeekExt, AsyncWriteExt};
+use tokio_stream::{Stream, StreamExt};
+
+/// 默认的下载连接数
+pub const DEFAULT_CONNECTIONS: usize = 4;
+/// 默认的连接超时秒数
+pub const DEFAULT_CONNECT_TIMEOUT: u64 = 10;
+/// 默认的等待响应超时秒数
+pub const DEFAULT_READ_TIMEOUT: u64 = 30;
+/// 默认的停滞超时秒数，超过该时间没有收到数据时重新连接
+pub const DEFAULT_STALL_TIMEOUT: u64 = 30;
+/// 默认的重试次数
+pub const DEFAULT_RETRIES: u32 = 5;
+/// 重试的最长等待时间
+const MAX_BACKOFF: Duration = Duration::from_secs(30);
+/// 每个分段的最小大小，小于该大小的文件不分段下载
+const MIN_SEGMENT_SIZE: u64 = 4 * 1024 * 1024;
+/// 每个分段每下载该字节数保存一次进度
+const SAVE_INTERVAL: u64 = 1024 * 1024;
+/// 从文件中补读数据计算哈希时每次读取的字节数
+const HASH_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// 下载选项
+#[derive(Debug, Clone)]
+pub struct DownloadOptions {
+    /// 可选的代理URL
+    pub proxy: Option<String>,
+    /// 同时下载的连接数，为 1 时不分段
+    pub connections: usize,
+    /// 文件的 SHA-512 校验值，与上次下载时不一致说明远程文件已更新
+    pub checksum: Option<String>,
+    /// 建立连接的超时时间
+    pub connect_timeout: Duration,
+    /// 发送请求后等待响应的超时时间
+    pub read_timeout: Duration,
+    /// 下载过程中没有收到数据的最长时间
+    pub stall_timeout: Duration,
+    /// 网络错误时的最大重试次数
+    pub retries: u32,
+    /// 校验失败的文件移动到该目录以便检查，为 None 时直接删除
+    pub quarantine: Option<PathBuf>,
+    /// 同时下载多个文件时共享的进度显示，为 None 时单独显示
+    pub progress: Option<MultiProgress>,
+}
+
+impl Default for DownloadOptions {
+    fn default() -> Self {
+        Self {
+            proxy: None,
+            connections: DEFAULT_CONNECTIONS,
+            checksum: None,
+            connect_timeout: Duration::from_secs(DEFAULT_CONNECT_TIMEOUT),
+            read_timeout: Duration::from_secs(DEFAULT_READ_TIMEOUT),
+            stall_timeout: Duration::from_secs(DEFAULT_STALL_TIMEOUT),
+            retries: DEFAULT_RETRIES,
+            quarantine: None,
+            progress: None,
+        }
+    }
+}
+
+/// 文件中的一段字节范围
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct Segment {
+    start: u64,
+    /// 结束位置（包含）
+    end: u64,
+    /// 已下载的字节数
+    downloaded: u64,
+}
+
+impl Segment {
+    fn len(&self) -> u64 {
+        self.end - self.start + 1
+    }
+    fn position(&self) -> u64 {
+        self.start + self.downloaded
+    }
+    fn is_done(&self) -> bool {
+        self.downloaded >= self.len()
+    }
+}
+
+/// 下载的进度，保存在 `.part.state` 文件中，用于断点续传
+///
+/// 续传前比较地址、ETag、文件大小和校验值，任意一项不一致都说明远程文件已变化，需要重新下载。
+/// 文件开头连续的已下载数据在写入时即计算 SHA-512，中间状态同样保存在进度文件中。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct DownloadState {
+    url: String,
+    etag: Option<String>,
+    size: u64,
+    checksum: Option<String>,
+    segments: Vec<Segment>,
+    /// 从文件开头已计算哈希的数据
+    #[serde(default)]
+    sha512: Sha512State,
+}
+
+impl DownloadState {
+    /// 将文件平均分为若干段
+    fn new(url: &str, remote: &Remote, checksum: Option<String>, connections: usize) -> Self {
+        let size = remote.size;
+        let count = (size / MIN_SEGMENT_SIZE).clamp(1, connections.max(1) as u64);
+        let step = size.div_ceil(count);
+        let segments = (0..count)
+            .map(|i| Segment {
+                start: i * step,
+                end: ((i + 1) * step).min(size) - 1,
+                downloaded: 0,
+            })
+            .collect();
+        Self {
+            url: url.to_string(),
+            etag: remote.etag.clone(),
+            size,
+            checksum,
+            segments,
+            sha512: Sha512State::default(),
+        }
+    }
+
+    /// 从已计算哈希的位置开始，连续写入的数据的结束位置
+    fn hashable_end(&self) -> u64 {
+        let hashed = self.sha512.len();
+        self.segments
+            .iter()
+            .find(|s| s.start <= hashed && hashed <= s.end)
+            .map_or(hashed, |s| s.position().max(hashed))
+    }
+
+    /// 进度文件是否属于同一个远程文件
+    fn matches(&self, url: &str, remote: &Remote, checksum: Option<&str>) -> bool {
+        self.url == url
+            && self.size == remote.size
+            && self.etag == remote.etag
+            && (checksum.is_none() || self.checksum.as_deref() == checksum)
+    }
+
+    /// 读取进度文件，文件不存在或已损坏时返回 None
+    fn load(path: &Path) -> Option<Self> {
+        load_json(path)
+            .ok()
+            .and_then(|json| serde_json::from_value(json).ok())
+    }
+
+    fn save(&self, path: &Path) -> Result<(), GdemError> {
+        save_json(&serde_json::to_value(self)?, path)
+    }
+
+    fn downloaded(&self) -> u64 {
+        self.segments
+            .iter()
+            .map(|s| s.downloaded.min(s.len()))
+            .sum()
+    }
+}
+
+/// 远程文件的信息
+#[derive(Debug, Clone)]
+struct Remote {
+    size: u64,
+    etag: Option<String>,
+}
+
+/// 探测请求的结果
+enum Probe {
+    /// 服务器支持 Range 请求
+    Ranged(Remote),
+    /// 服务器忽略了 Range 请求，返回了完整的文件
+    Full(Remote, Response),
+}
+
+fn get_header(headers: &HeaderMap, name: impl reqwest::header::AsHeaderName) -> Option<String> {
+    headers
+        .get(name)
+        .and_then(|h| h.to_str().ok())
+        .map(|h| h.to_string())
+}
+
+/// 使用完整文件的响应下载，文件大小取自 Content-Length
+fn full_probe(response: Response) -> Probe {
+    let headers = response.headers();
+    let etag = get_header(headers, ETAG);
+    let size = get_header(headers, CONTENT_LENGTH)
+        .and_then(|h| h.parse::<u64>().ok())
+        .unwrap_or(0);
+    Probe::Full(Remote { size, etag }, response)
+}
+
+/// 解析 Content-Range 响应头，格式为 `bytes 100-199/200`
+///
+/// # Returns
+///
+/// * `Option<(u64, u64, u64)>` - (起始位置, 结束位置, 文件总大小)
+fn parse_content_range(value: &str) -> Option<(u64, u64, u64)> {
+    let (range, total) = value.strip_prefix("bytes ")?.split_once('/')?;
+    let (start, end) = range.split_once('-')?;
+    Some((
+        start.trim().parse().ok()?,
+        end.trim().parse().ok()?,
+        total.trim().parse().ok()?,
+    ))
+}
+
+/// 网络错误可以重试，返回错误原因；文件不存在、磁盘错误等不会因重试而改变
+fn retry_reason(e: &GdemError) -> Option<&str> {
+    match e {
+        GdemError::Network(reason) => Some(reason),
+        _ => None,
+    }
+}
+
+/// 第 `attempt` 次重试前的等待时间：指数退避，并加上随机抖动避免多个连接同时重试
+fn backoff(attempt: u32) -> Duration {
+    let delay = Duration::from_secs(1 << attempt.saturating_sub(1).min(5)).min(MAX_BACKOFF);
+    let jitter = fastrand::u64(0..=delay.as_millis() as u64 / 2);
+    delay + Duration::from_millis(jitter)
+}
+
+/// 一次下载的上下文，在所有分段之间共享
+struct Transfer<'a> {
+    client: Client,
+    uri: &'a str,
+    options: &'a DownloadOptions,
+    pb: ProgressBar,
+    /// 文件名，用作进度条的消息
+    name: String,
+    /// 下载过程中发现远程文件变化的原因
+    changed: Mutex<Option<String>>,
+    /// 是否有分段正在从文件中补读数据计算哈希
+    hashing: AtomicBool,
+}
+
+impl Transfer<'_> {
+    /// 发送请求并等待响应，超时或服务器返回错误时返回错误
+    async fn send(&self, request: RequestBuilder) -> Result<Response, GdemError> {
+        let timeout = self.options.read_timeout;
+        let response = tokio::time::timeout(timeout, request.send())
+            .await
+            .map_err(|_| {
+                GdemError::Network(format!("no response within {}s", timeout.as_secs()))
+            })??;
+        let status = response.status();
+        // 除超时和请求过多外，客户端错误不会因重试而改变
+        if status.is_client_error()
+            && status != StatusCode::REQUEST_TIMEOUT
+            && status != StatusCode::TOO_MANY_REQUESTS
+        {
+            return Err(GdemError::NotFound(format!(
+                "{} returned {}",
+                self.uri, status
+            )));
+        }
+        Ok(response.error_for_status()?)
+    }
+
+    /// 读取下一块数据，超过停滞时间没有收到数据时返回错误
+    async fn next_chunk<S, B>(&self, stream: &mut S) -> Result<Option<B>, GdemError>
+    where
+        S: Stream<Item = reqwest::Result<B>> + Unpin,
+    {
+        let timeout = self.options.stall_timeout;
+        let chunk = tokio::time::timeout(timeout, stream.next())
+            .await
+            .map_err(|_| {
+                GdemError::Network(format!("no data received for {}s", timeout.as_secs()))
+            })?;
+        Ok(chunk.transpose()?)
+    }
+
+    /// 标记远程文件已变化，所有分段停止下载
+    fn set_changed(&self, reason: String) {
+        *self.changed.lock().unwrap_or_else(|e| e.into_inner()) = Some(reason);
+    }
+
+    fn take_changed(&self) -> Option<String> {
+        self.changed
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .take()
+    }
+
+    fn is_changed(&self) -> bool {
+        self.changed
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some()
+    }
+
+    /// 执行操作，遇到网络错误时等待一段时间后重试，并在进度条中显示重试次数
+    ///
+    /// 远程文件已变化时不再重试。
+    async fn retry<T, F, Fut>(&self, mut f: F) -> Result<T, GdemError>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = Result<T, GdemError>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match f().await {
+                Ok(value) => return Ok(value),
+                Err(e) if attempt < self.options.retries && !self.is_changed() => {
+                    let Some(reason) = retry_reason(&e) else {
+                        return Err(e);
+                    };
+                    attempt += 1;
+                    let delay = backoff(attempt);
+                    self.pb.set_message(format!(
+                        "{} (retry {}/{} in {}s: {})",
+                        self.name,
+                        attempt,
+                        self.options.retries,
+                        delay.as_secs(),
+                        reason
+                    ));
+                    tokio::time::sleep(delay).await;
+                }
+                Err(e) => return Err(e),
+            }
+        }
+    }
+
+    /// 请求文件的第一个字节，判断服务器是否支持 Range 请求，并获取文件大小和 ETag
+    ///
+    /// 服务器忽略 Range 返回 200 时，探测请求同时作为完整文件的下载请求，不会重复请求文件。
+    /// 其他响应（如 `bytes 0-0/*` 这样没有文件大小的 206）只包含部分内容，丢弃后重新请求完整文件。
+    async fn probe(&self) -> Result<Probe, GdemError> {
+        let request = self.client.get(self.uri).header(RANGE, "bytes=0-0");
+        let response = self.send(request).await?;
+        let range =
+            get_header(response.headers(), CONTENT_RANGE).and_then(|v| parse_content_range(&v));
+        match (response.status(), range) {
+            (StatusCode::PARTIAL_CONTENT, Some((0, 0, size))) => {
+                let etag = get_header(response.headers(), ETAG);
+                Ok(Probe::Ranged(Remote { size, etag }))
+            }
+            (StatusCode::OK, _) => Ok(full_probe(response)),
+            _ => {
+                drop(response);
+                let response = self.send(self.client.get(self.uri)).await?;
+                Ok(full_probe(response))
+            }
+        }
+    }
+
+    /// 将完整文件的响应写入文件，没有响应时重新请求
+    ///
+    /// # Returns
+    ///
+    /// * `Result<String, GdemError>` - 文件的 SHA-512
+    async fn download_stream(
+        &self,
+        response: Option<Response>,
+        part_path: &Path,
+    ) -> Result<String, GdemError> {
+        let response = match response {
+            Some(response) => response,
+            None => self.send(self.client.get(self.uri)).await?,
+        };
+        // 不支持 Range 请求时无法续传，每次都从头写入
+        let mut file = TokioFile::create(part_path).await?;
+        self.pb.set_position(0);
+
+        let mut sha512 = Sha512State::default();
+        let mut stream = response.bytes_stream();
+        while let Some(chunk) = self.next_chunk(&mut stream).await? {
+            file.write_all(&chunk).await?;
+            sha512.update(&chunk);
+            self.pb.inc(chunk.len() as u64);
+        }
+        file.flush().await?;
+        Ok(sha512.finish())
+    }
+
+    /// 通过多个连接同时下载所有未完成的分段
+    async fn download_segments(
+        &self,
+        part_path: &Path,
+        state_path: &Path,
+        state: &Mutex<DownloadState>,
+    ) -> Result<(), GdemError> {
+        let count = lock(state).segments.len();
+        let tasks = (0..count).map(|index| {
+            self.retry(move || self.download_segment(part_path, state_path, state, index))
+        });
+        try_join_all(tasks).await?;
+        Ok(())
+    }
+
+    /// 从已下载的位置继续下载一个分段，定期将进度写入进度文件
+    async fn download_segment(
+        &self,
+        part_path: &Path,
+        state_path: &Path,
+        state: &Mutex<DownloadState>,
+        index: usize,
+    ) -> Result<(), GdemError> {
+        let (segment, etag, size) = {
+            let state = lock(state);
+            (
+                state.segments[index].clone(),
+                state.etag.clone(),
+                state.size,
+            )
+        };
+        if segment.is_done() {
+            return Ok(());
+        }
+        let request = self.client.get(self.uri).header(
+            RANGE,
+            format!("bytes={}-{}", segment.position(), segment.end),
+        );
+        // 远程文件变化时，服务器会返回完整的文件而不是指定的范围
+        let request = match &etag {
+            Some(etag) if !etag.starts_with("W/") => request.header(IF_RANGE, etag),
+            _ => request,
+        };
+        let response = self.send(request).await?;
+
+        // 检查响应是否为请求的范围，以及文件是否与之前一致
+        let headers = response.headers();
+        let range = get_header(headers, CONTENT_RANGE).and_then(|v| parse_content_range(&v));
+        let response_etag = get_header(headers, ETAG);
+        let changed = if response.status() != StatusCode::PARTIAL_CONTENT {
+            Some(format!(
+                "the server answered {} instead of 206",
+                response.status()
+            ))
+        } else if !matches!(range, Some((start, _, total)) if start == segment.position() && total == size)
+        {
+            Some("the server answered an unexpected Content-Range".to_string())
+        } else if etag.is_some() && response_etag.is_some() && response_etag != etag {
+            Some("the ETag of the remote file changed".to_string())
+        } else {
+            None
+        };
+        if let Some(reason) = changed {
+            self.set_changed(reason.clone());
+            return Err(GdemError::Network(reason));
+        }
+        self.pb.set_message(self.name.clone());
+
+        let mut file = TokioFile::options().write(true).open(part_path).await?;
+        file.seek(SeekFrom::Start(segment.position())).await?;
+
+        let remaining = segment.len() - segment.downloaded;
+        let mut written: u64 = 0;
+        let mut unsaved: u64 = 0;
+        let mut stream = response.bytes_stream();
+        let result = async {
+            while written < remaining
+                && let Some(chunk) = self.next_chunk(&mut stream).await?
+            {
+                // 忽略超出分段范围的数据
+                let len = (chunk.len() as u64).min(remaining - written);
+                let data = &chunk[..len as usize];
+                // 写入完成后才能记录进度，补读哈希时会从文件中读取这些数据
+                file.write_all(data).await?;
+                file.flush().await?;
+                let catch_up = self.record(state, index, segment.position() + written, data);
+                written += len;
+                unsaved += len;
+                self.pb.inc(len);
+                if unsaved >= SAVE_INTERVAL {
+                    lock(state).save(state_path)?;
+                    unsaved = 0;
+                }
+                if catch_up {
+                    self.catch_up_hash(part_path, state).await?;
+                }
+            }
+            Ok::<(), GdemError>(())
+        }
+        .await;
+        // 出错时也保存已写入的进度，重试时从该位置继续
+        file.flush().await?;
+        lock(state).save(state_path)?;
+        result?;
+
+        if written < remaining {
+            return Err(GdemError::Network(format!(
+                "the connection closed after {} of {} bytes",
+                segment.downloaded + written,
+                segment.len()
+            )));
+        }
+        Ok(())
+    }
+
+    /// 记录分段写入的数据，数据紧接在已计算哈希的位置之后时直接计算哈希
+    ///
+    /// # Returns
+    ///
+    /// * `bool` - 是否需要从文件中补读其他分段已写入的数据
+    fn record(&self, state: &Mutex<DownloadState>, index: usize, pos: u64, data: &[u8]) -> bool {
+        let mut state = lock(state);
+        state.segments[index].downloaded += data.len() as u64;
+        if state.sha512.len() == pos {
+            state.sha512.update(data);
+        }
+        state.sha512.len() < state.hashable_end() && !self.hashing.load(Ordering::Acquire)
+    }
+
+    /// 从文件中读取已写入但尚未计算哈希的数据，直到追上正在下载的位置
+    ///
+    /// 同一时间只有一个分段补读，其他分段在数据紧接在哈希位置之后时直接计算哈希。
+    async fn catch_up_hash(
+        &self,
+        part_path: &Path,
+        state: &Mutex<DownloadState>,
+    ) -> Result<(), GdemError> {
+        while !self.hashing.swap(true, Ordering::AcqRel) {
+            let result = self.read_unhashed(part_path, state).await;
+            self.hashing.store(false, Ordering::Release);
+            result?;
+            // 补读结束后其他分段可能又推进了哈希位置
+            let state = lock(state);
+            if state.sha512.len() >= state.hashable_end() {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    async fn read_unhashed(
+        &self,
+        part_path: &Path,
+        state: &Mutex<DownloadState>,
+    ) -> Result<(), GdemError> {
+        let mut file = TokioFile::open(part_path).await?;
+        let mut buffer = vec![0u8; HASH_BUFFER_SIZE];
+        loop {
+            let (from, to) = {
+                let state = lock(state);
+                (state.sha512.len(), state.hashable_end())
+            };
+            if from >= to {
+                return Ok(());
+            }
+            let len = ((to - from) as usize).min(HASH_BUFFER_SIZE);
+            file.seek(SeekFrom::Start(from)).await?;
+            file.read_exact(&mut buffer[..len]).await?;
+            let mut state = lock(state);
+            // 期间其他分段可能已经直接计算了这部分数据的哈希
+            if state.sha512.len() == from {
+                state.sha512.update(&buffer[..len]);
+            }
+        }
+    }
+}
+
+/// 创建下载进度条，添加到共享的进度显示中
+fn new_download_bar(msg: String, progress: Option<&MultiProgress>) -> ProgressBar {
+    let pb = match progress {
+        Some(m) => m.add(ProgressBar::new(0)),
+        None => new_multi_progress().add(ProgressBar::new(0)),
+    };
+    pb.set_style(
+        ProgressStyle::with_template(
+            "[{elapsed_precise}] {bar:40.cyan/blue} {bytes:>7}/{total_bytes:7} ({percent:>3}%) {bytes_per_sec:9} {msg}",
+        )
+        .unwrap_or_else(|_| ProgressStyle::default_bar())
+        .progress_chars("##-"),
+    );
+    pb.set_message(msg);
+    pb
+}
+
+/// 异步下载文件到指定路径，并显示下载进度
+///
+/// 服务器支持 Range 请求时，文件被分为多段并通过多个连接同时下载，
+/// 每段的进度保存在 `.part.state` 文件中，中断后再次下载会从每段已下载的位置继续。
+/// 服务器不支持 Range 请求或连接数为 1 时，使用单个连接下载。
+/// 连接超时、停滞或断开时，等待一段时间后从已写入的位置重试。
+/// 下载时同时计算 SHA-512，提供了校验值时在下载结束时比较，不一致时删除文件并返回错误。
+///
+/// # Arguments
+///
+/// * `uri` - 要下载的文件的URL
+/// * `file_path` - 保存文件的本地路径
+/// * `options` - 下载选项
+///
+/// # Returns
+///
+/// * `Result<String, GdemError>` - 下载结果，成功返回提示信息，失败返回错误信息
+///
+/// # Example
+///
+/// ```no_run
+/// use gdem::core::download::{DownloadOptions, download_file};
+/// use std::path::Path;
+///
+/// # async fn run() -> Result<(), gdem::core::error::GdemError> {
+/// let uri = "https://downloads.tuxfamily.org/godotengine/4.0/Godot_v4.0-stable_win64.zip";
+/// let file_path = Path::new("Godot_v4.0-stable_win64.zip");
+/// let options = DownloadOptions {
+///     proxy: Some("http://127.0.0.1:7890".to_string()),
+///     connections: 8,
+///     ..Default::default()
+/// };
+///
+/// download_file(uri, file_path, &options).await?;
+/// # Ok(())
+/// # }
+/// ```
+pub async fn download_file(
+    uri: &str,
+    file_path: &Path,
+    options: &DownloadOptions,
+) -> Result<String, GdemError> {
+    let name = file_path
+        .file_name()
+        .and_then(|os_str| os_str.to_str()) // &OsStr -> Option<&str>
+        .unwrap_or("unknown") // 失败时提供默认值
+        .to_string();
+    // 目标文件存在说明之前已完整下载
+    if file_path.exists() {
+        if options.checksum.is_some() {
+            verify_digest(file_path, &name, options, sha512sum(file_path)?).await?;
+        }
+        return Ok("File already downloaded".to_string());
+    }
+
+    let client = client_builder(options.proxy.as_deref())
+        .connect_timeout(options.connect_timeout)
+        .build()?;
+    let transfer = Transfer {
+        client,
+        uri,
+        options,
+        pb: new_download_bar(name.clone(), options.progress.as_ref()),
+        name,
+        changed: Mutex::new(None),
+        hashing: AtomicBool::new(false),
+    };
+
+    // 远程文件在下载过程中变化时，删除已下载的部分并重新下载一次
+    let mut reason = String::new();
+    for _ in 0..2 {
+        match try_download(&transfer, file_path).await? {
+            Attempt::Done => {
+                transfer.pb.finish_with_message("✓");
+                return Ok("Download completed".to_string());
+            }
+            Attempt::Changed(changed) => reason = changed,
+        }
+    }
+    transfer.pb.abandon();
+    Err(GdemError::Network(format!(
+        "the download was restarted but the remote file still does not match: {}",
+        reason
+    )))
+}
+
+/// 一次下载的结果
+enum Attempt {
+    Done,
+    /// 远程文件已变化，已下载的部分已被删除
+    Changed(String),
+}
+
+async fn try_download(transfer: &Transfer<'_>, file_path: &Path) -> Result<Attempt, GdemError> {
+    // 下载过程中写入 .part 文件，完成后再重命名为目标文件
+    let part_path = partial_path(file_path);
+    let state_path = state_path(&part_path);
+    let options = transfer.options;
+    let pb = &transfer.pb;
+
+    let remote = match transfer.retry(|| transfer.probe()).await? {
+        Probe::Ranged(remote) => remote,
+        // 服务器不支持 Range 请求，只能从头下载
+        Probe::Full(remote, response) => {
+            remove_partial(&part_path, &state_path).await?;
+            pb.set_length(remote.size);
+            let mut response = Some(response);
+            let digest = transfer
+                .retry(|| transfer.download_stream(response.take(), &part_path))
+                .await?;
+            verify_digest(&part_path, &transfer.name, options, digest).await?;
+            rename(&part_path, file_path).await?;
+            return Ok(Attempt::Done);
+        }
+    };
+
+    // 继续未完成的下载，没有进度文件或远程文件已变化时重新开始
+    let state = DownloadState::load(&state_path).filter(|state| {
+        state.matches(transfer.uri, &remote, options.checksum.as_deref()) && part_path.exists()
+    });
+    let state = match state {
+        Some(state) => state,
+        None => {
+            remove_partial(&part_path, &state_path).await?;
+            let state = DownloadState::new(
+                transfer.uri,
+                &remote,
+                options.checksum.clone(),
+                options.connections,
+            );
+            // 预先分配文件大小，各分段写入各自的位置
+            TokioFile::create(&part_path)
+                .await?
+                .set_len(remote.size)
+                .await?;
+            state.save(&state_path)?;
+            state
+        }
+    };
+
+    let connections = state.segments.iter().filter(|s| !s.is_done()).count();
+    if connections > 1 {
+        pb.set_message(format!("{} ({} connections)", transfer.name, connections));
+    }
+    pb.set_length(remote.size);
+    pb.set_position(state.downloaded());
+    let state = Mutex::new(state);
+    if let Err(e) = transfer
+        .download_segments(&part_path, &state_path, &state)
+        .await
+    {
+        return match transfer.take_changed() {
+            Some(reason) => {
+                remove_partial(&part_path, &state_path).await?;
+                pb.set_message(format!("{} ({}, restarting)", transfer.name, reason));
+                Ok(Attempt::Changed(reason))
+            }
+            None => Err(e),
+        };
+    }
+    // 补读最后剩余的数据，得到完整文件的哈希
+    transfer.catch_up_hash(&part_path, &state).await?;
+    let sha512 = lock(&state).sha512.clone();
+    if sha512.len() != remote.size {
+        return Err(GdemError::Network(format!(
+            "only {} of {} bytes could be hashed",
+            sha512.len(),
+            remote.size
+        )));
+    }
+    remove_file(&state_path).await?;
+    verify_digest(&part_path, &transfer.name, options, sha512.finish()).await?;
+    rename(&part_path, file_path).await?;
+    Ok(Attempt::Done)
+}
+
+/// 比较下载文件的 SHA-512 与期望的校验值，不一致时隔离文件并返回错误
+async fn verify_digest(
+    path: &Path,
+    name: &str,
+    options: &DownloadOptions,
+    actual: String,
+) -> Result<(), GdemError> {
+    let Some(expected) = &options.checksum else {
+        return Ok(());
+    };
+    if expected.eq_ignore_ascii_case(&actual) {
+        return Ok(());
+    }
+    let quarantine = quarantine_file(path, name, options.quarantine.as_deref()).await?;
+    Err(GdemError::ChecksumMismatch {
+        file: name.to_string(),
+        expected: expected.clone(),
+        actual,
+        quarantine,
+    })
+}
+
+/// 将校验失败的文件移动到隔离目录，没有隔离目录时删除文件
+///
+/// 文件名加上时间戳，以免覆盖之前隔离的同名文件。
+///
+/// # Returns
+///
+/// * `Result<Option<PathBuf>, GdemError>` - 文件被移动到的位置，删除时为 None
+pub async fn quarantine_file(
+    path: &Path,
+    name: &str,
+    dir: Option<&Path>,
+) -> Result<Option<PathBuf>, GdemError> {
+    let Some(dir) = dir else {
+        remove_file(path).await?;
+        return Ok(None);
+    };
+    create_dir_all(dir).await?;
+    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let target = dir.join(format!("{}.{}", name, timestamp));
+    rename(path, &target).await?;
+    Ok(Some(target))
+}
+
+/// 删除未完成的下载及其进度文件
+async fn remove_partial(part_path: &Path, state_path: &Path) -> Result<(), GdemError> {
+    for path in [part_path, state_path] {
+        if path.exists() {
+            remove_file(path).await?;
+        }
+    }
+    Ok(())
+}
+
+fn lock(state: &Mutex<DownloadState>) -> std::sync::MutexGuard<'_, DownloadState> {
+    // 其他分段出错时不会在持有锁期间 panic，进度仍然可用
+    state.lock().unwrap_or_else(|e| e.into_inner())
+}
@@ -0,0 +1,184 @@
+use crate::core::utils::format_size;
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+/// gdem 的错误类型，每种错误对应一个退出码
+#[derive(Debug)]
+pub enum GdemError {
+    /// 版本、文件、引擎或目录不存在
+    NotFound(String),
+    /// 文件名匹配到多个文件
+    AmbiguousAsset {
+        query: String,
+        candidates: Vec<String>,
+    },
+    /// 网络请求失败
+    Network(String),
+    /// 下载文件的校验值不一致
+    ChecksumMismatch {
+        file: String,
+        expected: String,
+        actual: String,
+        /// 校验失败的文件被移动到的位置
+        quarantine: Option<PathBuf>,
+    },
+    /// 校验文件的签名无效
+    SignatureInvalid(String),
+    /// 压缩包中有不安全的路径、链接，或解压后超出限制
+    UnsafeArchive(String),
+    /// 读写文件失败
+    Io(io::Error),
+    /// 配置文件或版本数据损坏
+    ConfigCorrupt(String),
+    /// 没有权限读写文件或创建链接
+    Permission(String),
+    /// 磁盘空间不足，`required` 和 `available` 为字节数
+    InsufficientSpace {
+        path: PathBuf,
+        required: u64,
+        available: u64,
+    },
+    /// 参数不合法
+    InvalidInput(String),
+}
+
+impl GdemError {
+    /// 进程的退出码，每种错误不同，2 与 clap 的参数错误保持一致
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Self::InvalidInput(_) => 2,
+            Self::NotFound(_) => 3,
+            Self::AmbiguousAsset { .. } => 4,
+            Self::Network(_) => 5,
+            Self::ChecksumMismatch { .. } => 6,
+            Self::Io(_) => 7,
+            Self::ConfigCorrupt(_) => 8,
+            Self::Permission(_) => 9,
+            Self::InsufficientSpace { .. } => 10,
+            Self::SignatureInvalid(_) => 11,
+            Self::UnsafeArchive(_) => 12,
+        }
+    }
+
+    /// 机器可读的错误码，用于 JSON 输出
+    pub fn code(&self) -> &'static str {
+        match self {
+            Self::NotFound(_) => "not_found",
+            Self::AmbiguousAsset { .. } => "ambiguous_asset",
+            Self::Network(_) => "network",
+            Self::ChecksumMismatch { .. } => "checksum_mismatch",
+            Self::SignatureInvalid(_) => "signature_invalid",
+            Self::UnsafeArchive(_) => "unsafe_archive",
+            Self::Io(_) => "io",
+            Self::ConfigCorrupt(_) => "config_corrupt",
+            Self::Permission(_) => "permission",
+            Self::InsufficientSpace { .. } => "insufficient_space",
+            Self::InvalidInput(_) => "invalid_input",
+        }
+    }
+}
+
+impl fmt::Display for GdemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NotFound(msg) => write!(f, "{}", msg),
+            Self::AmbiguousAsset { query, candidates } => write!(
+                f,
+                "{} matches several assets, use the full name: {}",
+                query,
+                candidates.join(", ")
+            ),
+            Self::Network(msg) => write!(
+                f,
+                "Network error: {}, check the connection or set a proxy with `gdem config -p`",
+                msg
+            ),
+            Self::ChecksumMismatch {
+                file,
+                expected,
+                actual,
+                quarantine,
+            } => {
+                write!(
+                    f,
+                    "Checksum mismatch for {}\n  expected: {}\n  actual:   {}",
+                    file, expected, actual
+                )?;
+                match quarantine {
+                    Some(path) => write!(f, "\n  the file was moved to {}", path.display()),
+                    None => write!(f, "\n  the file was removed"),
+                }
+            }
+            Self::SignatureInvalid(msg) => write!(f, "Invalid signature: {}", msg),
+            Self::UnsafeArchive(msg) => write!(f, "Unsafe archive: {}", msg),
+            Self::Io(e) => write!(f, "IO error: {}", e),
+            Self::ConfigCorrupt(msg) => write!(f, "{}", msg),
+            Self::Permission(msg) => write!(
+                f,
+                "Permission denied: {}, check that GDEM_ROOT is writable",
+                msg
+            ),
+            Self::InsufficientSpace {
+                path,
+                required,
+                available,
+            } => write!(
+                f,
+                "Not enough disk space on {}: {} required, {} available, free up space or run `gdem cache prune`",
+                path.display(),
+                format_size(*required as f64),
+                format_size(*available as f64)
+            ),
+            Self::InvalidInput(msg) => write!(f, "{}", msg),
+        }
+    }
+}
+
+impl std::error::Error for GdemError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<io::Error> for GdemError {
+    fn from(e: io::Error) -> Self {
+        match e.kind() {
+            io::ErrorKind::PermissionDenied => Self::Permission(e.to_string()),
+            io::ErrorKind::NotFound => Self::NotFound(e.to_string()),
+            _ => Self::Io(e),
+        }
+    }
+}
+
+impl From<reqwest::Error> for GdemError {
+    fn from(e: reqwest::Error) -> Self {
+        Self::Network(e.to_string())
+    }
+}
+
+impl From<serde_json::Error> for GdemError {
+    fn from(e: serde_json::Error) -> Self {
+        Self::ConfigCorrupt(format!("Invalid JSON data: {}", e))
+    }
+}
+
+impl From<zip::result::ZipError> for GdemError {
+    fn from(e: zip::result::ZipError) -> Self {
+        match e {
+            zip::result::ZipError::Io(e) => e.into(),
+            e => Self::Io(io::Error::new(io::ErrorKind::InvalidData, e)),
+        }
+    }
+}
+
+/// 版本不存在的错误，提示更新版本数据
+pub fn not_found_version(version: &str) -> GdemError {
+    GdemError::NotFound(format!(
+        "Version {} not found, run `gdem ls -r` to see the available versions or remove data/releases.json and `gdem sync` again",
+        version
+    ))
+}
@@ -1,6 +1,7 @@
+use super::error::GdemError;
 use super::utils::load_json; // 引用项目中已有的JSON加载函数
 use serde_json::Value;
-use std::{error::Error, option::Option, path::Path};
+use std::{option::Option, path::Path};
 
 /// JSON文档处理器
 ///
@@ -33,9 +34,12 @@ impl DocumentHandler {
     /// # 返回值
     ///
     /// 返回包含加载数据的DocumentHandler实例，如果加载失败则返回错误
-    pub fn load_data(file_path: &Path) -> Result<Self, Box<dyn Error>> {
+    pub fn load_data(file_path: &Path) -> Result<Self, GdemError> {
         let document = load_json(file_path)?;
-        Ok(Self::new(document.as_array().unwrap().to_vec()))
+        let document = document.as_array().ok_or_else(|| {
+            GdemError::ConfigCorrupt(format!("{} is not a JSON array", file_path.display()))
+        })?;
+        Ok(Self::new(document.to_vec()))
     }
 }
 
@@ -50,13 +54,11 @@ impl DocumentHandler {
     /// # 返回值
     ///
     /// 返回包含匹配项的新DocumentHandler实例
-    pub fn query(&self, key: Option<&str>, value: Option<&Value>) -> Result<Self, Box<dyn Error>> {
+    pub fn query(&self, key: Option<&str>, value: Option<&Value>) -> Result<Self, GdemError> {
         // 如果未提供键或值，返回当前实例的克隆
-        if key.is_none() || value.is_none() {
+        let (Some(key), Some(value)) = (key, value) else {
             return Ok(self.clone());
-        }
-        let key = key.unwrap();
-        let value = value.unwrap();
+        };
         // 筛选符合条件的元素
         let filtered: Vec<Value> = self
             .document
@@ -80,7 +82,7 @@ impl DocumentHandler {
     pub fn rename(
         &mut self,
         name_map: &std::collections::HashMap<String, String>,
-    ) -> Result<(), Box<dyn Error>> {
+    ) -> Result<(), GdemError> {
         // 遍历每个对象并重命名字段
         for item in &mut self.document {
             if let Some(obj) = item.as_object_mut() {
@@ -104,7 +106,7 @@ impl DocumentHandler {
     /// # 返回值
     ///
     /// 返回包含提取字段的新文档
-    pub fn orderby(&mut self, levels: &[&str]) -> Result<Vec<Value>, Box<dyn Error>> {
+    pub fn orderby(&mut self, levels: &[&str]) -> Result<Vec<Value>, GdemError> {
         let array = &mut self.document;
         let mut new_doc = Vec::with_capacity(array.len());
 
@@ -132,7 +134,7 @@ impl DocumentHandler {
     /// # 返回值
     ///
     /// 如果排序成功则返回Ok，否则返回Err
-    pub fn sort(&mut self, key: &str, reverse: bool) -> Result<(), Box<dyn Error>> {
+    pub fn sort(&mut self, key: &str, reverse: bool) -> Result<(), GdemError> {
         let array = &mut self.document;
 
         array.sort_by(|a, b| {
@@ -163,7 +165,7 @@ impl DocumentHandler {
     /// # 返回值
     ///
     /// 如果应用成功则返回Ok，否则返回Err
-    pub fn map<F>(&mut self, f: F) -> Result<(), Box<dyn Error>>
+    pub fn map<F>(&mut self, f: F) -> Result<(), GdemError>
     where
         F: Fn(Value) -> Value,
     {
@@ -186,17 +188,17 @@ impl DocumentHandler {
     /// # 返回值
     ///
     /// 如果应用成功则返回Ok，否则返回Err
-    pub fn apply<F>(&mut self, key: &str, f: F) -> Result<(), Box<dyn Error>>
+    pub fn apply<F>(&mut self, key: &str, f: F) -> Result<(), GdemError>
     where
         F: Fn(Value) -> Value,
     {
         let array = &mut self.document;
 
         for item in array {
-            if let Some(obj) = item.as_object_mut() {
-                if let Some(value) = obj.remove(key) {
-                    obj.insert(key.to_string(), f(value));
-                }
+            if let Some(obj) = item.as_object_mut()
+                && let Some(value) = obj.remove(key)
+            {
+                obj.insert(key.to_string(), f(value));
             }
         }
 
@@ -212,7 +214,7 @@ impl DocumentHandler {
     /// # 返回值
     ///
     /// 返回包含满足条件的元素的新DocumentHandler实例
-    pub fn flitter<F>(&self, condition: F) -> Result<Self, Box<dyn Error>>
+    pub fn flitter<F>(&self, condition: F) -> Result<Self, GdemError>
     where
         F: Fn(&Value) -> bool,
     {
@@ -236,13 +238,15 @@ impl DocumentHandler {
     /// # 返回值
     ///
     /// 返回只包含指定字段的新DocumentHandler实例
-    pub fn get_specific_fields(&self, fields: &[&str]) -> Result<Self, Box<dyn Error>> {
+    pub fn get_specific_fields(&self, fields: &[&str]) -> Result<Self, GdemError> {
         let array = &self.document;
 
         let mut new_doc = Vec::with_capacity(array.len());
 
         for item in array {
-            let obj = item.as_object().ok_or("Array element is not an object")?;
+            let obj = item
+                .as_object()
+                .ok_or(GdemError::ConfigCorrupt("Array element is not an object".to_string()))?;
             let mut new_item = serde_json::Map::new();
 
             for &field in fields {
@@ -267,7 +271,7 @@ impl DocumentHandler {
     /// # 返回值
     ///
     /// 返回分组后的DocumentHandler实例
-    pub fn group_by<F>(&self, key: &str, agg_map: Option<F>) -> Result<Self, Box<dyn Error>>
+    pub fn group_by<F>(&self, key: &str, agg_map: Option<F>) -> Result<Self, GdemError>
     where
         F: Fn(Vec<Value>) -> Vec<Value>,
     {
@@ -281,42 +285,37 @@ impl DocumentHandler {
         // 遍历数组中的每个元素
         for item in array {
             // 确保元素是对象类型
-            let obj = item.as_object().ok_or("Array element is not an object")?;
+            let obj = item
+                .as_object()
+                .ok_or(GdemError::ConfigCorrupt("Array element is not an object".to_string()))?;
 
             // 获取用于分组的键值
             let key_value = obj
                 .get(key)
-                .ok_or(format!("Key '{}' not found in object", key))?;
+                .ok_or(GdemError::ConfigCorrupt(format!("Key '{}' not found in object", key)))?;
             let key_str = key_value
                 .as_str()
-                .ok_or(format!("Key '{}' is not a string", key))?
+                .ok_or(GdemError::ConfigCorrupt(format!("Key '{}' is not a string", key)))?
                 .to_string();
 
             // 如果键值不在分组数据中，则初始化
-            if !grouped_data.contains_key(&key_str) {
+            let group = grouped_data.entry(key_str).or_insert_with(|| {
                 let mut new_group = serde_json::Map::new();
                 new_group.insert(key.to_string(), key_value.clone());
-                grouped_data.insert(key_str.clone(), new_group);
-            }
+                new_group
+            });
 
             // 遍历对象中的每个键值对
             for (k, v) in obj {
                 // 跳过分组键
-                if k != key {
-                    // 如果键不在分组数据中，则初始化为空数组
-                    if !grouped_data.get(&key_str).unwrap().contains_key(k) {
-                        grouped_data
-                            .get_mut(&key_str)
-                            .unwrap()
-                            .insert(k.clone(), Value::Array(vec![]));
-                    }
-
+                if k != key
+                    && let Some(arr) = group
+                        .entry(k.clone())
+                        .or_insert_with(|| Value::Array(vec![]))
+                        .as_array_mut()
+                {
                     // 将值添加到数组中
-                    if let Some(arr) = grouped_data.get_mut(&key_str).unwrap().get_mut(k) {
-                        if let Some(arr) = arr.as_array_mut() {
-                            arr.push(v.clone());
-                        }
-                    }
+                    arr.push(v.clone());
                 }
             }
         }
@@ -325,11 +324,11 @@ impl DocumentHandler {
         if let Some(agg_fn) = agg_map {
             for group in grouped_data.values_mut() {
                 for (k, v) in group.clone() {
-                    if k != key {
-                        if let Some(arr) = v.as_array() {
-                            let new_arr = agg_fn(arr.clone());
-                            group.insert(k, Value::Array(new_arr));
-                        }
+                    if k != key
+                        && let Some(arr) = v.as_array()
+                    {
+                        let new_arr = agg_fn(arr.clone());
+                        group.insert(k, Value::Array(new_arr));
                     }
                 }
             }
@@ -0,0 +1,198 @@
+use crate::core::utils::{sha256sum, sha512sum};
+use serde::{Deserialize, Serialize};
+use sha2::compress512;
+use sha2::digest::generic_array::GenericArray;
+use std::collections::BTreeMap;
+use std::fmt;
+use std::io;
+use std::path::Path;
+
+/// SHA-512 的块大小
+const BLOCK_SIZE: usize = 128;
+
+/// SHA-512 的初始哈希值
+const INITIAL_STATE: [u64; 8] = [
+    0x6a09e667f3bcc908,
+    0xbb67ae8584caa73b,
+    0x3c6ef372fe94f82b,
+    0xa54ff53a5f1d36f1,
+    0x510e527fade682d1,
+    0x9b05688c2b3e6c1f,
+    0x1f83d9abfb41bd6b,
+    0x5be0cd19137e2179,
+];
+
+/// 可以保存中间状态的 SHA-512 哈希
+///
+/// 下载中断时将中间状态写入进度文件，续传时从中断的位置继续计算，无需重新读取已下载的数据。
+///
+/// # Examples
+///
+/// ```
+/// use gdem::core::hash::Sha512State;
+///
+/// let mut sha = Sha512State::default();
+/// sha.update(b"a");
+/// // 中间状态可以序列化后恢复
+/// let json = serde_json::to_string(&sha).unwrap();
+/// let mut sha: Sha512State = serde_json::from_str(&json).unwrap();
+/// sha.update(b"bc");
+/// assert_eq!(sha.finish(), Sha512State::digest(b"abc"));
+/// assert!(Sha512State::digest(b"abc").starts_with("ddaf35a193617aba"));
+/// ```
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Sha512State {
+    state: [u64; 8],
+    /// 不足一个块、尚未压缩的数据
+    buffer: Vec<u8>,
+    /// 已输入的字节数
+    length: u64,
+}
+
+impl Default for Sha512State {
+    fn default() -> Self {
+        Self {
+            state: INITIAL_STATE,
+            buffer: Vec::with_capacity(BLOCK_SIZE),
+            length: 0,
+        }
+    }
+}
+
+impl Sha512State {
+    /// 计算数据的 SHA-512，返回十六进制字符串
+    pub fn digest(data: &[u8]) -> String {
+        let mut sha = Self::default();
+        sha.update(data);
+        sha.finish()
+    }
+
+    /// 已输入的字节数
+    pub fn len(&self) -> u64 {
+        self.length
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.length == 0
+    }
+
+    pub fn update(&mut self, mut data: &[u8]) {
+        self.length += data.len() as u64;
+        // 先补齐缓冲区中的块
+        if !self.buffer.is_empty() {
+            let take = (BLOCK_SIZE - self.buffer.len()).min(data.len());
+            self.buffer.extend_from_slice(&data[..take]);
+            data = &data[take..];
+            if self.buffer.len() < BLOCK_SIZE {
+                return;
+            }
+            let block = std::mem::take(&mut self.buffer);
+            self.compress(&block);
+        }
+        let full = data.len() - data.len() % BLOCK_SIZE;
+        self.compress(&data[..full]);
+        self.buffer.extend_from_slice(&data[full..]);
+    }
+
+    /// 结束计算，返回十六进制字符串
+    pub fn finish(mut self) -> String {
+        let bits = (self.length as u128) * 8;
+        let mut tail = std::mem::take(&mut self.buffer);
+        tail.push(0x80);
+        // 填充到块长度减去 16 字节的长度字段
+        let padded = (tail.len() + 16).div_ceil(BLOCK_SIZE) * BLOCK_SIZE;
+        tail.resize(padded - 16, 0);
+        tail.extend_from_slice(&bits.to_be_bytes());
+        self.compress(&tail);
+        self.state
+            .iter()
+            .map(|word| format!("{:016x}", word))
+            .collect()
+    }
+
+    /// 压缩若干个完整的块
+    fn compress(&mut self, data: &[u8]) {
+        for block in data.chunks_exact(BLOCK_SIZE) {
+            compress512(&mut self.state, &[*GenericArray::from_slice(block)]);
+        }
+    }
+}
+
+/// 校验文件使用的哈希算法
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HashAlgorithm {
+    Sha512,
+    Sha256,
+}
+
+impl HashAlgorithm {
+    /// 按优先级排列的所有算法，同时提供时使用 SHA-512
+    pub const ALL: [HashAlgorithm; 2] = [Self::Sha512, Self::Sha256];
+
+    /// 校验文件名的前缀，如 `SHA512-SUMS.txt` 中的 `SHA512-SUMS`
+    pub fn sums_prefix(&self) -> &'static str {
+        match self {
+            Self::Sha512 => "SHA512-SUMS",
+            Self::Sha256 => "SHA256-SUMS",
+        }
+    }
+
+    /// 十六进制摘要的长度
+    pub fn hex_len(&self) -> usize {
+        match self {
+            Self::Sha512 => 128,
+            Self::Sha256 => 64,
+        }
+    }
+
+    /// 计算文件的摘要
+    pub fn file_digest(&self, path: &Path) -> io::Result<String> {
+        match self {
+            Self::Sha512 => sha512sum(path),
+            Self::Sha256 => sha256sum(path),
+        }
+    }
+}
+
+impl fmt::Display for HashAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Sha512 => write!(f, "SHA-512"),
+            Self::Sha256 => write!(f, "SHA-256"),
+        }
+    }
+}
+
+/// 解析 `sha512sum`、`sha256sum` 格式的校验文件，返回文件名到摘要的映射
+///
+/// 每行格式为 `<摘要>  <文件名>`，二进制模式的文件名前有 `*`。
+/// 文件名完整匹配，摘要长度不符或不是十六进制的行会被忽略。
+///
+/// # Examples
+///
+/// ```
+/// use gdem::core::hash::{HashAlgorithm, parse_sums};
+///
+/// let a = "a".repeat(64);
+/// let b = "B".repeat(64);
+/// let text = format!("{a}  Godot_v4.4.1-stable_mono_win64.zip\n{b} *Godot_v4.4.1-stable_win64.exe.zip\n\nbroken line\n");
+/// let sums = parse_sums(&text, HashAlgorithm::Sha256);
+/// assert_eq!(sums.len(), 2);
+/// assert_eq!(sums["Godot_v4.4.1-stable_win64.exe.zip"], "b".repeat(64));
+/// assert!(!sums.contains_key("win64.zip"));
+/// ```
+pub fn parse_sums(text: &str, algorithm: HashAlgorithm) -> BTreeMap<String, String> {
+    text.lines()
+        .filter_map(|line| {
+            let (digest, name) = line.trim().split_once(char::is_whitespace)?;
+            let name = name.trim_start();
+            let name = name.strip_prefix('*').unwrap_or(name);
+            let name = name.strip_prefix("./").unwrap_or(name);
+            let valid = digest.len() == algorithm.hex_len()
+                && digest.chars().all(|c| c.is_ascii_hexdigit())
+                && !name.is_empty();
+            valid.then(|| (name.to_string(), digest.to_ascii_lowercase()))
+        })
+        .collect()
+}
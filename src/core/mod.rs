@@ -2,5 +2,10 @@ pub mod tags;
 pub mod source;
 pub mod handler;
 pub mod utils;
+pub mod download;
+pub mod hash;
 pub mod config;
 pub mod style;
+pub mod output;
+pub mod catalog;
+pub mod error;
@@ -0,0 +1,148 @@
+use crate::core::error::GdemError;
+use clap::ValueEnum;
+use serde_json::{Value, json};
+use std::sync::atomic::{AtomicBool, Ordering};
+
+/// 机器可读输出的结构版本，字段发生不兼容的变化时递增
+pub const SCHEMA_VERSION: u64 = 1;
+
+/// 命令的输出格式
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum OutputFormat {
+    /// 带颜色的树状文本
+    #[default]
+    Text,
+    /// 带有结构版本的 JSON 文档
+    Json,
+    /// 无颜色、无装饰的文本，每行一项，字段以制表符分隔
+    Plain,
+}
+
+static QUIET: AtomicBool = AtomicBool::new(false);
+
+impl OutputFormat {
+    /// 应用输出格式：非文本模式下关闭颜色以及进度条
+    pub fn apply(&self) {
+        if *self != Self::Text {
+            colored::control::set_override(false);
+            QUIET.store(true, Ordering::Relaxed);
+        }
+    }
+}
+
+/// 是否隐藏进度条和提示信息
+pub fn is_quiet() -> bool {
+    QUIET.load(Ordering::Relaxed)
+}
+
+/// 命令的输出结果，同时包含结构化数据和文本
+#[derive(Debug, Clone)]
+pub struct Output {
+    pub data: Value,
+    pub text: String,
+    pub plain: Option<String>,
+    /// 部分失败时的退出码，如批量安装中有文件安装失败
+    pub exit_code: i32,
+}
+
+impl Output {
+    pub fn new(data: Value, text: impl Into<String>) -> Self {
+        Self {
+            data,
+            text: text.into(),
+            plain: None,
+            exit_code: 0,
+        }
+    }
+
+    /// 设置 plain 格式的输出，默认使用去除颜色后的文本
+    pub fn with_plain(mut self, plain: impl Into<String>) -> Self {
+        self.plain = Some(plain.into());
+        self
+    }
+
+    /// 设置退出码，结果仍会正常输出，JSON 中的 `ok` 为 false
+    pub fn with_exit_code(mut self, code: i32) -> Self {
+        self.exit_code = code;
+        self
+    }
+
+    /// 由表格的行生成 plain 格式的输出
+    pub fn with_plain_rows(self, rows: &[Vec<String>]) -> Self {
+        let plain = rows
+            .iter()
+            .map(|row| row.join("\t"))
+            .collect::<Vec<String>>()
+            .join("\n");
+        self.with_plain(plain)
+    }
+}
+
+/// 按输出格式打印命令结果
+///
+/// # Arguments
+///
+/// * `format` - 输出格式
+/// * `command` - 命令名，写入 JSON 文档的 `command` 字段
+/// * `label` - 文本模式下失败时的提示，如 `Install failed`
+/// * `result` - 命令结果
+///
+/// # Returns
+///
+/// * `i32` - 进程的退出码，成功时为 0
+pub fn emit(
+    format: OutputFormat,
+    command: &str,
+    label: &str,
+    result: Result<Output, GdemError>,
+) -> i32 {
+    let code = match &result {
+        Ok(output) => output.exit_code,
+        Err(e) => e.exit_code(),
+    };
+    match format {
+        OutputFormat::Json => {
+            let document = match result {
+                Ok(output) => json!({
+                    "schema_version": SCHEMA_VERSION,
+                    "command": command,
+                    "ok": code == 0,
+                    "data": output.data,
+                }),
+                Err(e) => json!({
+                    "schema_version": SCHEMA_VERSION,
+                    "command": command,
+                    "ok": false,
+                    "error": {
+                        "code": e.code(),
+                        "message": e.to_string(),
+                    },
+                }),
+            };
+            println!(
+                "{}",
+                serde_json::to_string_pretty(&document).unwrap_or_default()
+            );
+        }
+        OutputFormat::Plain => match result {
+            Ok(output) => {
+                let plain = output
+                    .plain
+                    .unwrap_or_else(|| console::strip_ansi_codes(&output.text).to_string());
+                if !plain.is_empty() {
+                    println!("{}", plain);
+                }
+            }
+            Err(e) => eprintln!("{}", e),
+        },
+        OutputFormat::Text => match result {
+            Ok(output) => {
+                if !output.text.is_empty() {
+                    println!("{}", output.text);
+                }
+            }
+            Err(e) => eprintln!("{}: {}", label, e),
+        },
+    }
+    code
+}
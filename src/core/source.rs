@@ -19,7 +19,7 @@ impl Source {
             Source::GodotEngine => "GodotEngine",
         }
     }
-    pub fn from_str(s: &str) -> Self {
+    pub fn from_name(s: &str) -> Self {
         match s {
             "GodotHub" => Source::GodotHub,
             "GodotEngine" => Source::GodotEngine,
@@ -1,13 +1,28 @@
 use colored::*;
-use indicatif::{ProgressBar, ProgressStyle};
+use crate::core::output::is_quiet;
+use console::measure_text_width;
+use indicatif::{MultiProgress, ProgressBar, ProgressDrawTarget, ProgressStyle};
+use regex::Regex;
 
 pub fn new_spinner() -> ProgressBar {
+    if is_quiet() {
+        return ProgressBar::hidden();
+    }
     let pb = ProgressBar::new_spinner();
     pb.set_style(ProgressStyle::default_spinner());
     pb.enable_steady_tick(std::time::Duration::from_millis(80));
     pb
 }
 
+/// 创建进度显示，多个进度条添加到同一个显示中时不会互相覆盖
+pub fn new_multi_progress() -> MultiProgress {
+    if is_quiet() {
+        MultiProgress::with_draw_target(ProgressDrawTarget::hidden())
+    } else {
+        MultiProgress::new()
+    }
+}
+
 pub fn show_list(versions: &[String], title: &str) -> String {
      let title = title.truecolor(128, 128, 128).to_string();
 
@@ -60,3 +75,150 @@ pub fn show_tree(versions: &[String], current: &str, title: &str) -> String {
 
     output.trim_end().to_string()
 }
+
+/// 版本的标记：当前版本为 `*` 并显示为绿色，已安装的版本为 `+`
+pub fn mark_version(text: &str, installed: bool, current: bool) -> String {
+    if current {
+        format!("*{}", text).green().to_string()
+    } else if installed {
+        format!("+{}", text).cyan().to_string()
+    } else {
+        format!(" {}", text)
+    }
+}
+
+/// 与 `show_tree` 相同，同时标记已安装的版本
+pub fn show_marked_list(
+    versions: &[String],
+    installed: &[String],
+    current: &str,
+    title: &str,
+) -> String {
+    let title = title.truecolor(128, 128, 128).to_string();
+
+    let mut output = format!("{}\n", title);
+    let last_index = versions.len().saturating_sub(1); // 防止下溢
+
+    for (index, version) in versions.iter().enumerate() {
+        let text = format!("{}) {}", index + 1, version);
+        let text = mark_version(&text, installed.contains(version), version == current);
+        let prefix = if index == last_index {
+            "└──"
+        } else {
+            "├──"
+        };
+        output.push_str(&format!("{} {}\n", prefix, text));
+    }
+
+    output.trim_end().to_string()
+}
+
+/// 多层级树的节点
+#[derive(Debug, Clone, Default)]
+pub struct TreeNode {
+    pub label: String,
+    pub children: Vec<TreeNode>,
+}
+
+impl TreeNode {
+    pub fn new(label: impl Into<String>, children: Vec<TreeNode>) -> Self {
+        Self {
+            label: label.into(),
+            children,
+        }
+    }
+}
+
+pub fn show_nested_tree(nodes: &[TreeNode], title: &str) -> String {
+    fn push_nodes(output: &mut String, nodes: &[TreeNode], indent: &str) {
+        let last_index = nodes.len().saturating_sub(1); // 防止下溢
+        for (index, node) in nodes.iter().enumerate() {
+            let (prefix, child_indent) = if index == last_index {
+                ("└──", "    ")
+            } else {
+                ("├──", "│   ")
+            };
+            output.push_str(&format!("{}{} {}\n", indent, prefix, node.label));
+            push_nodes(output, &node.children, &format!("{}{}", indent, child_indent));
+        }
+    }
+
+    let title = title.truecolor(128, 128, 128).to_string();
+    let mut output = format!("{}\n", title);
+    push_nodes(&mut output, nodes, "");
+    output.trim_end().to_string()
+}
+
+pub fn show_table(headers: &[&str], rows: &[Vec<String>], title: &str) -> String {
+    let title = title.truecolor(128, 128, 128).to_string();
+
+    // 计算每一列的宽度，忽略颜色控制字符
+    let mut widths: Vec<usize> = headers.iter().map(|h| measure_text_width(h)).collect();
+    for row in rows {
+        for (i, cell) in row.iter().enumerate() {
+            if i < widths.len() {
+                widths[i] = widths[i].max(measure_text_width(cell));
+            }
+        }
+    }
+    let format_row = |cells: Vec<String>| -> String {
+        let mut line = String::new();
+        for (i, cell) in cells.iter().enumerate() {
+            let width = widths.get(i).copied().unwrap_or(0);
+            let pad = width.saturating_sub(measure_text_width(cell));
+            line.push_str(cell);
+            line.push_str(&" ".repeat(pad + 2));
+        }
+        line.trim_end().to_string()
+    };
+
+    let header = format_row(headers.iter().map(|h| h.to_string()).collect());
+    let mut output = format!("{}\n    {}\n", title, header.truecolor(128, 128, 128));
+    let last_index = rows.len().saturating_sub(1); // 防止下溢
+
+    for (index, row) in rows.iter().enumerate() {
+        let prefix = if index == last_index {
+            "└──"
+        } else {
+            "├──"
+        };
+
+        let line = format!("{} {}\n", prefix, format_row(row.clone()));
+        output.push_str(&line);
+    }
+
+    output.trim_end().to_string()
+}
+
+/// 将 Markdown 格式的发布说明渲染为终端文本
+///
+/// 标题加粗，列表使用 `•`，`**粗体**` 加粗，`` `代码` `` 显示为青色，链接只保留文字。
+pub fn render_markdown(text: &str) -> String {
+    let link = Regex::new(r"\[([^\]]+)\]\([^)]+\)").unwrap();
+    let bold = Regex::new(r"\*\*([^*]+)\*\*").unwrap();
+    let code = Regex::new(r"`([^`]+)`").unwrap();
+
+    let mut output = String::new();
+    for line in text.lines() {
+        let line = line.trim_end();
+        let line = link.replace_all(line, "$1");
+        let line = bold.replace_all(&line, |c: &regex::Captures| c[1].bold().to_string());
+        let line = code.replace_all(&line, |c: &regex::Captures| c[1].cyan().to_string());
+
+        let trimmed = line.trim_start();
+        let indent = &line[..line.len() - trimmed.len()];
+        let rendered = if trimmed.starts_with('#') {
+            trimmed.trim_start_matches('#').trim().bold().to_string()
+        } else if let Some(item) = trimmed
+            .strip_prefix("- ")
+            .or_else(|| trimmed.strip_prefix("* "))
+        {
+            format!("{}  • {}", indent, item)
+        } else {
+            line.to_string()
+        };
+        output.push_str(&rendered);
+        output.push('\n');
+    }
+    output.trim_end().to_string()
+}
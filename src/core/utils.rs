@@ -1,213 +1,516 @@
+use crate::core::error::GdemError;
+use flate2::Compression;
 use flate2::read::GzDecoder;
-use indicatif::{MultiProgress, ProgressBar, ProgressStyle};
+use flate2::write::GzEncoder;
 use reqwest::ClientBuilder;
 use ring::digest::{Context, SHA256, SHA512};
+use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use std::fs::{
     self, File, read_dir, read_to_string, remove_dir, remove_dir_all, remove_file, rename,
 };
 use std::io::{self, Read, Write};
-use std::path::Path;
-use tar::Archive;
-use tokio::fs::File as TokioFile;
-use tokio::io::AsyncWriteExt;
-use tokio_stream::StreamExt;
+use std::path::{Path, PathBuf};
+use tar::{Archive, Builder, EntryType};
 use zip::ZipArchive;
 
+/// 解压的结果
+#[derive(Debug, Default, Clone)]
+pub struct ExtractReport {
+    /// 解压的文件、目录和链接数
+    pub extracted: usize,
+    /// 跳过的条目及原因
+    pub skipped: Vec<(String, String)>,
+}
+
+/// 默认的解压总大小上限，导出模板解压后约 1GB
+pub const DEFAULT_MAX_EXTRACT_SIZE: u64 = 8 * 1024 * 1024 * 1024;
+/// 默认的解压条目数上限
+pub const DEFAULT_MAX_EXTRACT_ENTRIES: u64 = 100_000;
+
+/// 解压的限制，下载源和镜像提供的压缩包不可信，防止压缩炸弹
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ExtractLimits {
+    /// 解压后的总大小上限（字节）
+    pub max_size: u64,
+    /// 条目数上限
+    pub max_entries: u64,
+}
+
+impl Default for ExtractLimits {
+    fn default() -> Self {
+        Self {
+            max_size: DEFAULT_MAX_EXTRACT_SIZE,
+            max_entries: DEFAULT_MAX_EXTRACT_ENTRIES,
+        }
+    }
+}
+
+/// 解压过程中已使用的大小和条目数
+struct ExtractBudget {
+    limits: ExtractLimits,
+    size: u64,
+    entries: u64,
+}
+
+impl ExtractBudget {
+    fn new(limits: &ExtractLimits) -> Self {
+        Self {
+            limits: *limits,
+            size: 0,
+            entries: 0,
+        }
+    }
+
+    fn add_entry(&mut self, name: &str) -> Result<(), GdemError> {
+        self.entries += 1;
+        if self.entries > self.limits.max_entries {
+            return Err(unsafe_archive(
+                name,
+                &format!("more than {} entries", self.limits.max_entries),
+            ));
+        }
+        Ok(())
+    }
+
+    /// 剩余可以写入的字节数
+    fn remaining(&self) -> u64 {
+        self.limits.max_size.saturating_sub(self.size)
+    }
+
+    fn add_size(&mut self, size: u64, name: &str) -> Result<(), GdemError> {
+        self.size = self.size.saturating_add(size);
+        if self.size > self.limits.max_size {
+            return Err(unsafe_archive(
+                name,
+                &format!(
+                    "the archive expands to more than {}",
+                    format_size(self.limits.max_size as f64)
+                ),
+            ));
+        }
+        Ok(())
+    }
+}
+
+fn unsafe_archive(name: &str, reason: &str) -> GdemError {
+    GdemError::UnsafeArchive(format!("{}: {}", name, reason))
+}
+
+/// 检查压缩包中条目的路径，返回相对于解压目录的路径
+///
+/// 绝对路径、盘符和 `..` 都会被拒绝，`\` 也视为分隔符。
+///
+/// # Examples
+///
+/// ```
+/// use gdem::core::utils::archive_entry_path;
+/// use std::path::PathBuf;
+///
+/// assert_eq!(archive_entry_path("./a/b.txt").unwrap(), PathBuf::from("a/b.txt"));
+/// assert!(archive_entry_path("../evil").is_err());
+/// assert!(archive_entry_path("a\\..\\..\\evil").is_err());
+/// assert!(archive_entry_path("/etc/passwd").is_err());
+/// assert!(archive_entry_path("C:/evil").is_err());
+/// ```
+pub fn archive_entry_path(name: &str) -> Result<PathBuf, GdemError> {
+    if name.starts_with(['/', '\\']) || name.split(['/', '\\']).next().is_some_and(|p| p.contains(':')) {
+        return Err(unsafe_archive(name, "absolute path"));
+    }
+    let mut path = PathBuf::new();
+    for part in name.split(['/', '\\']) {
+        match part {
+            "" | "." => {}
+            ".." => return Err(unsafe_archive(name, "path contains `..`")),
+            part => path.push(part),
+        }
+    }
+    if path.as_os_str().is_empty() {
+        return Err(unsafe_archive(name, "empty path"));
+    }
+    Ok(path)
+}
+
+/// 检查符号链接的目标，目标必须在解压目录内
+///
+/// 从链接所在目录的规范路径开始解析目标，经过已解压的符号链接时按链接实际指向的位置计算，
+/// 防止 `b -> .`、`c -> b/..` 这样单独检查都在解压目录内、组合起来指向外面的链接。
+///
+/// # Arguments
+/// * `root` - 解压目录的规范路径
+/// * `relative` - 链接相对于解压目录的路径
+/// * `target` - 链接指向的路径
+fn check_link_target(root: &Path, relative: &Path, target: &str) -> Result<(), GdemError> {
+    let name = relative.to_string_lossy();
+    if target.starts_with(['/', '\\']) || target.split(['/', '\\']).next().is_some_and(|p| p.contains(':')) {
+        return Err(unsafe_archive(&name, &format!("link to absolute path {}", target)));
+    }
+    let outside = || unsafe_archive(&name, &format!("link points outside to {}", target));
+    let parents = relative
+        .parent()
+        .into_iter()
+        .flat_map(|p| p.components())
+        .map(|c| c.as_os_str().to_string_lossy().to_string());
+    let parts = target.split(['/', '\\']).map(str::to_string);
+    let mut resolved = root.to_path_buf();
+    // 路径中有还不存在的部分时，之后解压的条目可能在这里创建链接，此后的 `..` 无法确定位置
+    let mut missing = false;
+    for part in parents.chain(parts) {
+        match part.as_str() {
+            "" | "." => {}
+            ".." if missing => {
+                return Err(unsafe_archive(&name, &format!("link target {} cannot be resolved", target)));
+            }
+            ".." => {
+                resolved.pop();
+            }
+            part => {
+                resolved.push(part);
+                if !missing {
+                    match fs::canonicalize(&resolved) {
+                        Ok(path) => resolved = path,
+                        Err(_) => missing = true,
+                    }
+                }
+            }
+        }
+        if !resolved.starts_with(root) {
+            return Err(outside());
+        }
+    }
+    Ok(())
+}
+
+/// 确认写入的位置在解压目录内，防止通过已解压的符号链接写到外面
+///
+/// # Arguments
+/// * `root` - 解压目录的规范路径
+/// * `path` - 将要写入的路径
+fn check_inside(root: &Path, path: &Path, name: &str) -> Result<(), GdemError> {
+    // 从最近的已存在的上级目录判断，条目所在的目录可能还没有创建
+    let mut existing = path.parent();
+    while let Some(dir) = existing {
+        if dir.symlink_metadata().is_ok() {
+            if !fs::canonicalize(dir)?.starts_with(root) {
+                return Err(unsafe_archive(name, "path leads outside through a symlink"));
+            }
+            return Ok(());
+        }
+        existing = dir.parent();
+    }
+    Ok(())
+}
+
 /// 解压缩zip文件到指定文件夹
 ///
+/// 保留压缩包中记录的 Unix 权限和符号链接，macOS 的 `.app` 中包含框架的符号链接。
+/// 路径不安全、符号链接指向解压目录外或超出限制时返回 `UnsafeArchive` 错误，
+/// 已解压的部分需要由调用者清理。当前系统无法创建的条目会被跳过，并记录在结果中。
+///
 /// # Arguments
 /// * `zip_file` - zip文件的路径
 /// * `target_folder` - 目标文件夹的路径
+/// * `limits` - 解压总大小和条目数的限制
 ///
 /// # Returns
-/// * `Result<(), Box<dyn std::error::Error>>` - 解压缩结果
+/// * `Result<ExtractReport, GdemError>` - 解压缩结果
 pub fn extract_zip(
     zip_file: &Path,
     target_folder: &Path,
-) -> Result<(), Box<dyn std::error::Error>> {
-    std::fs::create_dir_all(&target_folder)?;
+    limits: &ExtractLimits,
+) -> Result<ExtractReport, GdemError> {
+    std::fs::create_dir_all(target_folder)?;
+    let root = fs::canonicalize(target_folder)?;
     let file = File::open(zip_file)?;
     let mut archive = ZipArchive::new(file)?;
-    archive.extract(target_folder)?;
-    promote_if_single_subdir(&target_folder)?;
-    Ok(())
+    let mut report = ExtractReport::default();
+    let mut budget = ExtractBudget::new(limits);
+    // 目录的权限在最后设置，以免只读目录中无法写入文件
+    let mut dir_modes: Vec<(PathBuf, u32)> = Vec::new();
+
+    for i in 0..archive.len() {
+        let mut entry = archive.by_index(i)?;
+        let name = entry.name().to_string();
+        budget.add_entry(&name)?;
+        let relative = archive_entry_path(&name)?;
+        let path = root.join(&relative);
+        check_inside(&root, &path, &name)?;
+        let mode = entry.unix_mode();
+
+        if entry.is_dir() {
+            fs::create_dir_all(&path)?;
+            if let Some(mode) = mode {
+                dir_modes.push((path, mode));
+            }
+            report.extracted += 1;
+            continue;
+        }
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent)?;
+        }
+        if entry.is_symlink() {
+            let mut link_target = String::new();
+            (&mut entry).take(4096).read_to_string(&mut link_target)?;
+            check_link_target(&root, &relative, &link_target)?;
+            match create_symlink(&link_target, &path) {
+                Ok(()) => report.extracted += 1,
+                Err(e) => report.skipped.push((name, e.to_string())),
+            }
+            continue;
+        }
+        // 先按声明的大小检查，写入时再按实际大小检查，声明的大小可能是伪造的
+        if entry.size() > budget.remaining() {
+            budget.add_size(entry.size(), &name)?;
+        }
+        // 同名的符号链接会被替换，而不是写入链接指向的文件
+        if path.symlink_metadata().is_ok_and(|m| m.is_symlink()) {
+            remove_file(&path)?;
+        }
+        let mut out = File::create(&path)?;
+        let written = io::copy(&mut (&mut entry).take(budget.remaining() + 1), &mut out)?;
+        budget.add_size(written, &name)?;
+        if let Some(mode) = mode {
+            set_mode(&path, mode)?;
+        }
+        report.extracted += 1;
+    }
+    for (path, mode) in dir_modes.into_iter().rev() {
+        set_mode(&path, mode)?;
+    }
+
+    promote_if_single_subdir(target_folder)?;
+    Ok(report)
+}
+
+/// 创建压缩包中的符号链接，已存在同名文件时先删除
+fn create_symlink(link_target: &str, path: &Path) -> io::Result<()> {
+    if path.symlink_metadata().is_ok() {
+        remove_file(path)?;
+    }
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(link_target, path)
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = link_target;
+        Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "symlinks are not supported on this system",
+        ))
+    }
+}
+
+/// 设置压缩包中记录的 Unix 权限，其他系统忽略
+///
+/// 只保留读写执行权限，setuid、setgid 和 sticky 位会被忽略。
+fn set_mode(path: &Path, mode: u32) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o777))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = (path, mode);
+        Ok(())
+    }
+}
+
+/// 为文件添加可执行权限，其他系统忽略
+pub fn set_executable(path: &Path) -> io::Result<()> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = fs::metadata(path)?.permissions().mode();
+        // 有读权限的用户同时获得执行权限
+        let exec = (mode & 0o444) >> 2;
+        fs::set_permissions(path, fs::Permissions::from_mode(mode | exec | 0o100))
+    }
+    #[cfg(not(unix))]
+    {
+        let _ = path;
+        Ok(())
+    }
+}
+
+/// 获取zip文件中记录的解压后总大小，读取目录即可，无需解压
+///
+/// # Returns
+/// * `Result<Option<u64>, GdemError>` - 解压后的字节数，有条目使用数据描述符而未记录大小时返回 None
+pub fn zip_extracted_size(zip_file: &Path) -> Result<Option<u64>, GdemError> {
+    let archive = ZipArchive::new(File::open(zip_file)?)?;
+    Ok(archive
+        .decompressed_size()
+        .map(|size| u64::try_from(size).unwrap_or(u64::MAX)))
+}
+
+/// 读取zip文件中指定条目的文本内容
+///
+/// # Arguments
+/// * `zip_file` - zip文件的路径
+/// * `entry_name` - 条目在压缩包中的路径，如 `templates/version.txt`
+///
+/// # Returns
+/// * `Result<Option<String>, GdemError>` - 条目内容，条目不存在时返回 None
+pub fn read_zip_entry(
+    zip_file: &Path,
+    entry_name: &str,
+) -> Result<Option<String>, GdemError> {
+    let file = File::open(zip_file)?;
+    let mut archive = ZipArchive::new(file)?;
+    let entry = match archive.by_name(entry_name) {
+        Ok(entry) => entry,
+        Err(zip::result::ZipError::FileNotFound) => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    // 只用于读取 version.txt 等小文件
+    let mut content = String::new();
+    entry.take(64 * 1024).read_to_string(&mut content)?;
+    Ok(Some(content))
 }
 
 /// 解压缩tar.gz文件到指定文件夹
 ///
+/// 与 zip 相同，拒绝不安全的路径、指向解压目录外的链接和超出限制的压缩包。
+///
 /// # Arguments
 /// * `tar_gz_file` - tar.gz文件的路径
 /// * `target_folder` - 目标文件夹的路径
+/// * `limits` - 解压总大小和条目数的限制
 ///
 /// # Returns
-/// * `Result<(), Box<dyn std::error::Error>>` - 解压缩结果
+/// * `Result<ExtractReport, GdemError>` - 解压缩结果
 pub fn extract_tar_gz(
     tar_gz_file: &Path,
     target_folder: &Path,
-) -> Result<(), Box<dyn std::error::Error>> {
+    limits: &ExtractLimits,
+) -> Result<ExtractReport, GdemError> {
+    fs::create_dir_all(target_folder)?;
+    let root = fs::canonicalize(target_folder)?;
     let file = File::open(tar_gz_file)?;
     let gz_decoder = GzDecoder::new(file);
     let mut archive = Archive::new(gz_decoder);
-    archive.unpack(target_folder)?;
-    Ok(())
-}
-
-// 构建带有可选代理的客户端
-pub fn build_client(proxy_url: Option<&str>) -> Result<reqwest::Client, reqwest::Error> {
-    let mut builder = ClientBuilder::new();
-
-    if let Some(proxy_str) = proxy_url {
-        if let Ok(proxy) = reqwest::Proxy::all(proxy_str) {
-            builder = builder.proxy(proxy);
+    let mut report = ExtractReport::default();
+    let mut budget = ExtractBudget::new(limits);
+
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        let name = entry.path()?.to_string_lossy().to_string();
+        budget.add_entry(&name)?;
+        let relative = archive_entry_path(&name)?;
+        let kind = entry.header().entry_type();
+        match kind {
+            EntryType::Symlink | EntryType::Link => {
+                let link = entry
+                    .link_name()?
+                    .map(|l| l.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                if kind == EntryType::Symlink {
+                    check_link_target(&root, &relative, &link)?;
+                } else {
+                    // 硬链接的目标是压缩包中的路径
+                    let target = root.join(archive_entry_path(&link)?);
+                    check_inside(&root, &target, &name)?;
+                }
+            }
+            EntryType::Regular | EntryType::Directory | EntryType::Continuous => {}
+            _ => {
+                report
+                    .skipped
+                    .push((name, format!("unsupported entry type {:?}", kind)));
+                continue;
+            }
+        }
+        budget.add_size(entry.size(), &name)?;
+        check_inside(&root, &root.join(&relative), &name)?;
+        if entry.unpack_in(&root)? {
+            report.extracted += 1;
+        } else {
+            report.skipped.push((name, "unsafe path".to_string()));
         }
     }
-
-    builder.build()
+    Ok(report)
 }
-/// 异步下载文件到指定路径，并显示下载进度
+
+/// 将多个文件或目录打包为tar.gz文件
 ///
 /// # Arguments
-///
-/// * `uri` - 要下载的文件的URL
-/// * `file_path` - 保存文件的本地路径
-/// * `proxy_url` - 可选的代理URL
+/// * `entries` - 要打包的本地路径以及它在压缩包中的路径
+/// * `tar_gz_file` - 生成的tar.gz文件的路径
 ///
 /// # Returns
-///
-/// * `Result<(), Box<dyn std::error::Error + Send + Sync>>` - 下载结果，成功返回Ok(())，失败返回错误信息
-///
-/// # Example
-///
-/// ```
-/// use godot_engine_manager::core::utils::download_file;
-/// use std::path::Path;
-///
-/// let uri = "https://downloads.tuxfamily.org/godotengine/4.0/Godot_v4.0-stable_win64.zip";
-/// let file_path = Path::new("Godot_v4.0-stable_win64.zip");
-/// let proxy_url = Some("http://127.0.0.1:7890");
-///
-/// download_file(uri, file_path, proxy_url).await.unwrap();
-/// ```
-
-///
-
-/// 获取远程文件的总大小
-async fn get_remote_file_size(
-    client: &reqwest::Client,
-    uri: &str,
-    start_pos: u64,
-) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
-    // 构建请求，添加Range头以支持断点续传
-    let request = client.get(uri);
-    let request = if start_pos > 0 {
-        request.header("Range", format!("bytes={}-", start_pos))
-    } else {
-        request
-    };
-
-    let response = request.send().await?;
-    let total_size = response
-        .headers()
-        .get("content-length")
-        .and_then(|h| h.to_str().ok())
-        .and_then(|h| h.parse::<u64>().ok())
-        .unwrap_or(0);
-
-    // 如果是断点续传，需要获取实际的总文件大小
-    let total_size = if start_pos > 0 {
-        // 从Content-Range头获取总大小
-        if let Some(content_range) = response.headers().get("content-range") {
-            if let Ok(content_range_str) = content_range.to_str() {
-                // 格式: bytes 100-199/200
-                if let Some(total) = content_range_str.split('/').nth(1) {
-                    total.parse::<u64>().unwrap_or(total_size + start_pos)
-                } else {
-                    total_size + start_pos
-                }
-            } else {
-                total_size + start_pos
-            }
+/// * `Result<(), GdemError>` - 打包结果
+pub fn create_tar_gz(
+    entries: &[(PathBuf, String)],
+    tar_gz_file: &Path,
+) -> Result<(), GdemError> {
+    if let Some(parent) = tar_gz_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let file = File::create(tar_gz_file)?;
+    let encoder = GzEncoder::new(file, Compression::default());
+    let mut builder = Builder::new(encoder);
+    builder.follow_symlinks(false);
+    for (path, name) in entries {
+        if path.is_dir() {
+            builder.append_dir_all(name, path)?;
         } else {
-            total_size + start_pos
+            builder.append_path_with_name(path, name)?;
         }
-    } else {
-        total_size
-    };
-
-    Ok(total_size)
+    }
+    builder.into_inner()?.finish()?;
+    Ok(())
 }
 
-pub async fn download_file(
-    uri: &str,
-    file_path: &Path,
-    proxy_url: Option<&str>,
-) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    let client = build_client(proxy_url)?;
+/// 列出tar.gz文件中的所有条目路径
+pub fn list_tar_gz(tar_gz_file: &Path) -> Result<Vec<String>, GdemError> {
+    let file = File::open(tar_gz_file)?;
+    let mut archive = Archive::new(GzDecoder::new(file));
+    let mut names = Vec::new();
+    for entry in archive.entries()? {
+        let entry = entry?;
+        names.push(entry.path()?.to_string_lossy().to_string());
+    }
+    Ok(names)
+}
 
-    // 检查本地已存在的文件大小
-    let start_pos = if file_path.exists() {
-        let metadata = tokio::fs::metadata(file_path).await?;
-        metadata.len()
-    } else {
-        0
-    };
+// 构建带有可选代理的客户端
+pub fn build_client(proxy_url: Option<&str>) -> Result<reqwest::Client, reqwest::Error> {
+    client_builder(proxy_url).build()
+}
 
-    // 获取远程文件总大小
-    let total_size = get_remote_file_size(&client, uri, start_pos).await?;
+// 创建带有可选代理的客户端构建器，用于进一步设置超时等选项
+pub fn client_builder(proxy_url: Option<&str>) -> ClientBuilder {
+    let mut builder = ClientBuilder::new();
 
-    // 如果本地文件已完全下载，则直接返回
-    if start_pos == total_size && total_size > 0 {
-        return Ok("File already downloaded".to_string());
+    if let Some(proxy_str) = proxy_url
+        && let Ok(proxy) = reqwest::Proxy::all(proxy_str)
+    {
+        builder = builder.proxy(proxy);
     }
 
-    // 重新发送请求以获取响应流
-    let request = client.get(uri);
-    let request = if start_pos > 0 {
-        request.header("Range", format!("bytes={}-", start_pos))
-    } else {
-        request
-    };
-    let response = request.send().await?;
-
-    let m = MultiProgress::new();
-    let pb = m.add(ProgressBar::new(total_size));
-    pb.set_style(
-        ProgressStyle::with_template(
-            "[{elapsed_precise}] {bar:40.cyan/blue} {bytes:>7}/{total_bytes:7} ({percent:>3}%) {bytes_per_sec:9} {msg}",
-        )?
-        .progress_chars("##-"),
-    );
-    let msg = file_path
-        .file_name()
-        .and_then(|os_str| os_str.to_str()) // &OsStr -> Option<&str>
-        .unwrap_or("unknown"); // 失败时提供默认值
-
-    pb.set_message(msg.to_string());
-    pb.set_position(start_pos);
-
-    // 以追加模式打开文件
-    let mut file = if start_pos > 0 {
-        TokioFile::options().append(true).open(file_path).await?
-    } else {
-        TokioFile::create(file_path).await?
-    };
+    builder
+}
 
-    let mut stream = response.bytes_stream();
-    let mut downloaded: u64 = start_pos;
+/// 未完成下载的临时文件后缀
+pub const PARTIAL_SUFFIX: &str = ".part";
 
-    while let Some(chunk) = stream.next().await {
-        let chunk = chunk?;
-        file.write_all(&chunk).await?;
-        let chunk_len = chunk.len() as u64;
-        downloaded += chunk_len;
-        pb.set_position(downloaded);
-    }
+/// 获取下载文件对应的临时文件路径，如 `xxx.zip` -> `xxx.zip.part`
+pub fn partial_path(file_path: &Path) -> PathBuf {
+    let mut path = file_path.as_os_str().to_owned();
+    path.push(PARTIAL_SUFFIX);
+    PathBuf::from(path)
+}
 
-    file.flush().await?;
-    pb.finish_with_message("✓");
+/// 分段下载进度文件的后缀
+pub const STATE_SUFFIX: &str = ".state";
 
-    Ok("Download completed".to_string())
+/// 获取临时文件对应的下载进度文件路径，如 `xxx.zip.part` -> `xxx.zip.part.state`
+pub fn state_path(part_path: &Path) -> PathBuf {
+    let mut path = part_path.as_os_str().to_owned();
+    path.push(STATE_SUFFIX);
+    PathBuf::from(path)
 }
 
 /// 计算文件的 SHA-256 哈希，返回十六进制字符串
@@ -255,33 +558,39 @@ pub fn sha512sum<P: AsRef<Path>>(file_path: P) -> io::Result<String> {
         .collect())
 }
 
-pub fn save_json(json: &Value, file_path: &Path) -> Result<(), Box<dyn std::error::Error>> {
+pub fn save_json(json: &Value, file_path: &Path) -> Result<(), GdemError> {
     let mut file = File::create(file_path)?;
     file.write_all(serde_json::to_string_pretty(json)?.as_bytes())?;
     Ok(())
 }
 
-pub fn load_json(file_path: &Path) -> Result<Value, Box<dyn std::error::Error>> {
+pub fn load_json(file_path: &Path) -> Result<Value, GdemError> {
     let json = serde_json::from_str(&read_to_string(file_path)?)?;
     Ok(json)
 }
 
 // 创建系统链接，适配多个系统
-pub fn symlink(original: &Path, link: &Path) -> Result<(), Box<dyn std::error::Error>> {
-    // println!("Create link: {} -> {}", original.display(), link.display());
-    // // 清理现有的 链接或目录
-    if link.exists() {
-        if link.is_dir() {
-            fs::remove_dir_all(link)?;
+//
+// 已存在的链接或空目录会被替换；已存在文件或非空目录时返回错误，
+// 调用者需要先将其中的数据复制到别处再删除，避免丢失数据。
+pub fn symlink(original: &Path, link: &Path) -> Result<(), GdemError> {
+    if let Ok(meta) = link.symlink_metadata() {
+        if meta.is_symlink() {
+            // Windows 下目录链接需要用 remove_dir 删除
+            fs::remove_file(link).or_else(|_| fs::remove_dir(link))?;
+        } else if meta.is_dir() && fs::read_dir(link)?.next().is_none() {
+            fs::remove_dir(link)?;
         } else {
-            fs::remove_file(link)?;
+            return Err(GdemError::Io(io::Error::new(
+                io::ErrorKind::AlreadyExists,
+                format!(
+                    "{} already exists and is not empty, refusing to replace it with a link",
+                    link.display()
+                ),
+            )));
         }
     }
 
-    if link.exists() {
-        remove_file(link)?;
-    }
-
     #[cfg(unix)]
     std::os::unix::fs::symlink(original, link)?;
     #[cfg(windows)]
@@ -342,8 +651,8 @@ impl UrlParams {
 /// * `target_folder` - 目标文件夹的路径
 ///
 /// # Returns
-/// * `Result<bool, Box<dyn std::error::Error>>` - 如果只有一个子文件夹并成功移动和删除则返回true，否则返回false
-pub fn move_and_clean_subfolder(target_folder: &Path) -> Result<bool, Box<dyn std::error::Error>> {
+/// * `Result<bool, GdemError>` - 如果只有一个子文件夹并成功移动和删除则返回true，否则返回false
+pub fn move_and_clean_subfolder(target_folder: &Path) -> Result<bool, GdemError> {
     let mut folders = Vec::new();
 
     // 遍历目标文件夹中的所有项目
@@ -410,6 +719,60 @@ pub fn format_size(size: f64) -> String {
     }
 }
 
+/// 递归计算目录下所有文件的总大小，不跟随符号链接
+pub fn dir_size(path: &Path) -> io::Result<u64> {
+    Ok(dir_stats(path)?.1)
+}
+
+/// 递归统计目录下的文件数量和总大小，不跟随符号链接
+///
+/// # Returns
+///
+/// * `io::Result<(u64, u64)>` - (文件数量, 总字节数)
+pub fn dir_stats(path: &Path) -> io::Result<(u64, u64)> {
+    let mut count = 0;
+    let mut size = 0;
+    for entry in read_dir(path)? {
+        let entry = entry?;
+        let file_type = entry.file_type()?;
+        if file_type.is_dir() {
+            let (sub_count, sub_size) = dir_stats(&entry.path())?;
+            count += sub_count;
+            size += sub_size;
+        } else if file_type.is_file() {
+            count += 1;
+            size += entry.metadata()?.len();
+        }
+    }
+    Ok((count, size))
+}
+
+/// 将人类可读的大小字符串解析为字节数，是 `format_size` 的逆操作
+///
+/// # Arguments
+///
+/// * `size` - 大小字符串，如 `500MB`、`2GB`、`1.5G`、`1024`
+///
+/// # Returns
+///
+/// * `Option<u64>` - 解析得到的字节数，格式不正确时返回 None
+pub fn parse_size(size: &str) -> Option<u64> {
+    let size = size.trim().to_uppercase();
+    let split = size
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(size.len());
+    let (num, unit) = size.split_at(split);
+    let num: f64 = num.parse().ok()?;
+    let unit = match unit.trim().trim_end_matches('B') {
+        "" => 1.0,
+        "K" => 1024.0,
+        "M" => 1024.0 * 1024.0,
+        "G" => 1024.0 * 1024.0 * 1024.0,
+        _ => return None,
+    };
+    Some((num * unit) as u64)
+}
+
 pub fn promote_if_single_subdir(target_folder: &Path) -> io::Result<()> {
     if !target_folder.is_dir() {
         return Err(io::Error::new(
@@ -436,8 +799,16 @@ pub fn promote_if_single_subdir(target_folder: &Path) -> io::Result<()> {
         let entry = &entries[0];
         let inner_path = entry.path();
 
-        if inner_path.is_dir() {
+        // macOS 的 Godot.app 是应用包，需要保留，编辑器位于 Godot.app/Contents/MacOS 中
+        let is_bundle = entry.file_name().to_string_lossy().ends_with(".app");
+        if inner_path.is_dir() && !is_bundle {
             let parent_dir = target_folder;
+            // 先改名，子目录中可能有与它同名的文件，如 Godot_v4.4.1-stable_linux.x86_64
+            let inner_path = parent_dir.join(format!(
+                ".{}.promote",
+                entry.file_name().to_string_lossy()
+            ));
+            fs::rename(entry.path(), &inner_path)?;
 
             // 将 inner_path 中的所有内容移动到 parent_dir
             for inner_entry in fs::read_dir(&inner_path)? {
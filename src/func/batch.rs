@@ -0,0 +1,233 @@
+use crate::core::error::GdemError;
+use crate::core::style::new_multi_progress;
+use crate::func::config::Config;
+use crate::func::install::{
+    InstallOptions, download_asset, extract_asset, query_digest, remove_cached_if_forced,
+};
+use crate::func::preflight::preflight;
+use crate::func::templates::query_template_asset;
+use crate::func::tool::{extract_tag, format_engine_name, get_asset_name};
+use futures_util::stream::{self, StreamExt};
+use serde::Deserialize;
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+/// 同时下载的文件数，每个文件还会按配置使用多个连接
+const PARALLEL_DOWNLOADS: usize = 3;
+
+/// 批量安装的清单文件，如 gdem.toml
+///
+/// ```toml
+/// engines = ["Godot_v4.4.1-stable_win64.exe.zip", "Godot_v4.3-stable_mono_win64.zip"]
+///
+/// [[templates]]
+/// version = "4.4.1"
+/// mono = true
+/// ```
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct InstallManifest {
+    /// 要安装的引擎，与 `gdem install` 的参数相同
+    #[serde(default)]
+    pub engines: Vec<String>,
+    /// 要安装的导出模板，与 `gdem templates install` 的参数相同
+    #[serde(default)]
+    pub templates: Vec<TemplatesSpec>,
+}
+
+impl InstallManifest {
+    /// 清单中的所有项，引擎在前
+    pub fn specs(&self) -> Vec<InstallSpec> {
+        let engines = self.engines.iter().cloned().map(InstallSpec::Engine);
+        let templates = self.templates.iter().cloned().map(InstallSpec::Templates);
+        engines.chain(templates).collect()
+    }
+}
+
+/// 导出模板的版本和类型
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TemplatesSpec {
+    /// 引擎版本，如 4.4.1 或 4.3-stable
+    pub version: String,
+    #[serde(default)]
+    pub mono: bool,
+}
+
+/// 批量安装中的一项
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InstallSpec {
+    /// 引擎文件名，可以是唯一匹配的部分文件名
+    Engine(String),
+    Templates(TemplatesSpec),
+}
+
+impl InstallSpec {
+    /// 解析为 releases.json 中完整的文件名
+    fn resolve(&self, cfg: &Config) -> Result<String, GdemError> {
+        match self {
+            Self::Engine(engine) => get_asset_name(engine, &cfg.data),
+            Self::Templates(spec) => query_template_asset(&spec.version, spec.mono, &cfg.data),
+        }
+    }
+}
+
+impl fmt::Display for InstallSpec {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Engine(engine) => write!(f, "{}", engine),
+            Self::Templates(spec) if spec.mono => write!(f, "templates {} (mono)", spec.version),
+            Self::Templates(spec) => write!(f, "templates {}", spec.version),
+        }
+    }
+}
+
+/// 读取批量安装的清单文件
+pub fn load_install_manifest(path: &Path) -> Result<InstallManifest, GdemError> {
+    let text = fs::read_to_string(path).map_err(|e| match e.kind() {
+        io::ErrorKind::NotFound => GdemError::NotFound(format!("{} not found", path.display())),
+        _ => GdemError::from(e),
+    })?;
+    let manifest: InstallManifest = toml::from_str(&text)
+        .map_err(|e| GdemError::InvalidInput(format!("Invalid {}: {}", path.display(), e)))?;
+    if manifest.engines.is_empty() && manifest.templates.is_empty() {
+        Err(GdemError::InvalidInput(format!(
+            "{} lists no engines or templates",
+            path.display()
+        )))?
+    }
+    Ok(manifest)
+}
+
+/// 批量安装中一个文件的结果
+#[derive(Debug)]
+pub struct BatchItem {
+    /// 命令行或清单文件中的写法
+    pub spec: String,
+    /// 完整的文件名
+    pub asset: String,
+    /// 成功时为安装的引擎或导出模板名
+    pub result: Result<String, GdemError>,
+}
+
+/// 批量安装引擎和导出模板
+///
+/// 先解析所有文件名并获取摘要，某一项失败时记录为失败，其他项继续安装。
+/// 磁盘空间或权限检查失败时所有文件都无法安装，直接返回错误，不开始下载。
+/// 之后同时下载多个文件，再依次解压，先解压引擎，导出模板的安装目录取决于已安装的引擎。
+/// 单个文件下载、校验或解压失败不影响其他文件，结果中记录每个文件的成功或失败。
+///
+/// # Arguments
+///
+/// * `specs` - 要安装的引擎和导出模板
+/// * `with_templates` - 同时安装每个引擎相同版本和类型的导出模板
+/// * `cfg` - 配置
+/// * `options` - 安装选项
+pub async fn batch_install(
+    specs: &[InstallSpec],
+    with_templates: bool,
+    cfg: &Config,
+    options: &InstallOptions,
+) -> Result<Vec<BatchItem>, GdemError> {
+    // (写法, 文件名)，同一个文件只安装一次，无法解析的项没有文件名
+    let mut items: Vec<(String, String)> = Vec::new();
+    // 每一项的结果，None 表示还在安装中
+    let mut results: Vec<Option<Result<String, GdemError>>> = Vec::new();
+    let mut add = |spec: String, asset: Result<String, GdemError>| match asset {
+        Ok(asset) if items.iter().any(|(_, a)| *a == asset) => {}
+        Ok(asset) => {
+            items.push((spec, asset));
+            results.push(None);
+        }
+        Err(e) => {
+            items.push((spec, "-".to_string()));
+            results.push(Some(Err(e)));
+        }
+    };
+    for spec in specs {
+        let asset = spec.resolve(cfg);
+        let templates = match (spec, &asset) {
+            (InstallSpec::Engine(engine), Ok(asset)) if with_templates => Some((
+                format!("{} templates", engine),
+                engine_templates(asset, cfg),
+            )),
+            _ => None,
+        };
+        add(spec.to_string(), asset);
+        if let Some((spec, templates)) = templates {
+            add(spec, templates);
+        }
+    }
+
+    for (i, (_, asset)) in items.iter().enumerate() {
+        if results[i].is_none()
+            && let Err(e) = remove_cached_if_forced(asset, cfg, options).await
+        {
+            results[i] = Some(Err(e));
+        }
+    }
+    let pending: Vec<usize> = (0..items.len()).filter(|&i| results[i].is_none()).collect();
+    let assets: Vec<String> = pending.iter().map(|&i| items[i].1.clone()).collect();
+    preflight(&assets, cfg)?;
+    // 同一版本的文件共用一个校验文件，依次获取以免同时下载和写入
+    let mut downloads = Vec::new();
+    for &i in &pending {
+        match query_digest(&items[i].1, cfg, options).await {
+            Ok(digest) => downloads.push((i, digest)),
+            Err(e) => results[i] = Some(Err(e)),
+        }
+    }
+
+    let progress = new_multi_progress();
+    let downloaded: Vec<(usize, Result<String, GdemError>)> = stream::iter(&downloads)
+        .map(|(i, digest)| async {
+            let result =
+                download_asset(&items[*i].1, cfg, digest.as_ref(), options, &progress).await;
+            (*i, result)
+        })
+        .buffered(PARALLEL_DOWNLOADS)
+        .collect()
+        .await;
+    let mut extract: Vec<usize> = Vec::new();
+    for (i, result) in downloaded {
+        match result {
+            Ok(_) => extract.push(i),
+            Err(e) => results[i] = Some(Err(e)),
+        }
+    }
+
+    extract.sort_by_key(|&i| items[i].1.ends_with(".tpz"));
+    for i in extract {
+        let asset = &items[i].1;
+        let result = extract_asset(asset, cfg, options).map(|_| format_engine_name(asset));
+        results[i] = Some(result);
+    }
+
+    Ok(items
+        .into_iter()
+        .zip(results)
+        .map(|((spec, asset), result)| {
+            // 每一项在解析、获取摘要、下载或解压时都已记录结果
+            let result = result.unwrap_or_else(|| {
+                Err(GdemError::InvalidInput(format!(
+                    "{} was not installed",
+                    asset
+                )))
+            });
+            BatchItem {
+                spec,
+                asset,
+                result,
+            }
+        })
+        .collect())
+}
+
+/// 获取与引擎相同版本和类型的导出模板文件名
+fn engine_templates(asset: &str, cfg: &Config) -> Result<String, GdemError> {
+    let tag = extract_tag(asset)
+        .ok_or_else(|| GdemError::InvalidInput(format!("Version of {} not found", asset)))?;
+    query_template_asset(&tag, asset.contains("_mono"), &cfg.data)
+}
@@ -0,0 +1,234 @@
+use crate::core::error::GdemError;
+use crate::core::utils::{PARTIAL_SUFFIX, STATE_SUFFIX, dir_size, state_path};
+use crate::func::config::Config;
+use crate::func::templates::get_template_roots;
+use crate::func::tool::{
+    extract_version, format_engine_name, get_levels_path, get_template_dir, get_tpz_template_dir,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+/// 校验失败的文件所在的目录，位于缓存目录下
+pub const QUARANTINE_DIR: &str = ".quarantine";
+
+/// 缓存目录中的一个文件
+#[derive(Debug, Clone)]
+pub struct CacheEntry {
+    pub name: String,
+    pub version: String,
+    pub path: PathBuf,
+    pub size: u64,
+    pub modified: SystemTime,
+    pub installed: bool,
+    pub partial: bool,
+}
+
+impl CacheEntry {
+    /// 文件最后修改距今的天数
+    pub fn age_days(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(self.modified)
+            .unwrap_or_default()
+            .as_secs()
+            / 86400
+    }
+
+    /// 是否为可清理的归档文件（引擎、导出模板、AAR 库或未完成的下载）
+    pub fn is_archive(&self) -> bool {
+        self.partial
+            || [".zip", ".tpz", ".aar"]
+                .iter()
+                .any(|ext| self.name.ends_with(ext))
+    }
+}
+
+/// 清理缓存的规则，多个规则同时生效
+#[derive(Debug, Default, Clone)]
+pub struct PruneOptions {
+    /// 删除已安装引擎或模板的归档
+    pub installed: bool,
+    /// 删除超过指定天数的归档
+    pub older_than: Option<u64>,
+    /// 缓存总大小上限，超出时从最旧的归档开始删除
+    pub max_size: Option<u64>,
+}
+
+impl PruneOptions {
+    pub fn is_empty(&self) -> bool {
+        !self.installed && self.older_than.is_none() && self.max_size.is_none()
+    }
+}
+
+/// 判断缓存中的归档是否已经安装
+pub fn is_installed(name: &str, cfg: &Config) -> bool {
+    if extract_version(name).is_none() {
+        return false;
+    }
+    if name.ends_with(".zip") {
+        get_levels_path(&cfg.home, name)
+            .map(|dir| dir.join(format_engine_name(name)).exists())
+            .unwrap_or(false)
+    } else if name.ends_with(".tpz") {
+        // 与安装时相同，目录名取自缓存的压缩包中的 version.txt
+        let file_path = get_levels_path(&cfg.cache, name).map(|dir| dir.join(name));
+        let template_dir = match file_path {
+            Ok(path) if path.is_file() => {
+                get_tpz_template_dir(&path, name).unwrap_or_else(|_| get_template_dir(name))
+            }
+            _ => get_template_dir(name),
+        };
+        get_template_roots(cfg)
+            .unwrap_or_default()
+            .iter()
+            .any(|root| root.path.join(&template_dir).exists())
+    } else {
+        false
+    }
+}
+
+/// 判断文件是否已经完整下载到缓存中
+pub fn is_cached(name: &str, cfg: &Config) -> bool {
+    get_levels_path(&cfg.cache, name)
+        .map(|dir| dir.join(name).is_file())
+        .unwrap_or(false)
+}
+
+/// 列出缓存目录中的所有文件
+///
+/// 缓存目录结构为 `cache/<major>/<version>/<file>`，以 `.part` 结尾的文件为未完成的下载，
+/// 以 `.part.state` 结尾的分段下载进度文件和 `.quarantine` 中校验失败的文件不会列出。
+pub fn list_cache(cfg: &Config) -> Result<Vec<CacheEntry>, GdemError> {
+    let mut entries: Vec<CacheEntry> = Vec::new();
+
+    let major_entries = match cfg.cache.read_dir() {
+        Ok(entries) => entries,
+        Err(_) => return Ok(entries),
+    };
+
+    // 遍历主版本目录 (如 4.x, 3.x)
+    for major_entry in major_entries {
+        let major_path = major_entry?.path();
+        if !major_path.is_dir() || major_path.ends_with(QUARANTINE_DIR) {
+            continue;
+        }
+
+        // 遍历版本目录 (如 4.4, 4.4.1)
+        for version_entry in major_path.read_dir()? {
+            let version_path = version_entry?.path();
+            if !version_path.is_dir() {
+                continue;
+            }
+            let version = version_path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+
+            for file_entry in version_path.read_dir()? {
+                let file_entry = file_entry?;
+                let metadata = file_entry.metadata()?;
+                if !metadata.is_file() {
+                    continue;
+                }
+                let file_name = file_entry.file_name().to_string_lossy().to_string();
+                // 分段下载的进度文件随 .part 文件一起处理
+                if file_name.ends_with(STATE_SUFFIX) {
+                    continue;
+                }
+                let partial = file_name.ends_with(PARTIAL_SUFFIX);
+                let name = file_name.trim_end_matches(PARTIAL_SUFFIX).to_string();
+                let installed = !partial && is_installed(&name, cfg);
+                entries.push(CacheEntry {
+                    name,
+                    version: version.clone(),
+                    path: file_entry.path(),
+                    size: metadata.len(),
+                    modified: metadata.modified().unwrap_or(SystemTime::UNIX_EPOCH),
+                    installed,
+                    partial,
+                });
+            }
+        }
+    }
+
+    entries.sort_by(|a, b| a.version.cmp(&b.version).then(a.name.cmp(&b.name)));
+    Ok(entries)
+}
+
+/// 按规则清理缓存中的归档文件
+///
+/// # Arguments
+///
+/// * `cfg` - 配置对象
+/// * `options` - 清理规则
+///
+/// # Returns
+///
+/// * `Result<Vec<CacheEntry>, GdemError>` - 被删除的文件列表
+pub fn prune_cache(
+    cfg: &Config,
+    options: &PruneOptions,
+) -> Result<Vec<CacheEntry>, GdemError> {
+    let entries = list_cache(cfg)?;
+    let mut total: u64 = entries.iter().map(|e| e.size).sum();
+    let mut removed: Vec<CacheEntry> = Vec::new();
+    let mut kept: Vec<CacheEntry> = Vec::new();
+
+    for entry in entries.into_iter().filter(|e| e.is_archive()) {
+        let expired = options
+            .older_than
+            .is_some_and(|days| entry.age_days() >= days);
+        if (options.installed && entry.installed) || expired {
+            total -= entry.size;
+            removed.push(entry);
+        } else {
+            kept.push(entry);
+        }
+    }
+
+    // 超出大小上限时，从最旧的文件开始删除
+    if let Some(max_size) = options.max_size {
+        kept.sort_by_key(|e| e.modified);
+        for entry in kept {
+            if total <= max_size {
+                break;
+            }
+            total -= entry.size;
+            removed.push(entry);
+        }
+    }
+
+    for entry in &removed {
+        fs::remove_file(&entry.path)?;
+        let state = state_path(&entry.path);
+        if entry.partial && state.exists() {
+            fs::remove_file(state)?;
+        }
+    }
+    Ok(removed)
+}
+
+/// 清空缓存目录
+///
+/// # Returns
+///
+/// * `Result<u64, GdemError>` - 释放的字节数
+pub fn clean_cache(cache: &Path) -> Result<u64, GdemError> {
+    if !cache.exists() {
+        return Ok(0);
+    }
+    let size = dir_size(cache)?;
+    fs::remove_dir_all(cache)?;
+    fs::create_dir_all(cache)?;
+    Ok(size)
+}
+
+/// 将文件修改时间格式化为距今的天数，如 `3d`
+pub fn format_age(days: u64) -> String {
+    if days == 0 {
+        "today".to_string()
+    } else {
+        format!("{}d", days)
+    }
+}
@@ -0,0 +1,282 @@
+use crate::core::catalog::Asset;
+use crate::core::download::{download_file, quarantine_file};
+use crate::core::error::GdemError;
+use crate::core::hash::{HashAlgorithm, parse_sums};
+use crate::core::source::format_url;
+use crate::core::utils::{load_json, save_json};
+use crate::func::cache::{CacheEntry, list_cache};
+use crate::func::config::Config;
+use crate::func::tool::{format_engine_name, get_levels_dir, load_remote_engine_assets};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::Path;
+use std::process::Command;
+
+/// 解析后的校验文件，保存在版本的缓存目录中
+const MANIFEST_FILE: &str = "checksums.json";
+
+/// 文件期望的摘要
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Digest {
+    pub algorithm: HashAlgorithm,
+    pub value: String,
+}
+
+/// 校验文件签名的检查结果，签名无效时直接返回错误，不会缓存
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Signature {
+    /// 签名有效
+    Verified,
+    /// 没有检查签名，如没有安装 gpg 或缺少公钥
+    Unchecked(String),
+}
+
+/// 一个发布版本的校验文件
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    /// 校验文件名，如 SHA512-SUMS.txt
+    pub name: String,
+    pub algorithm: HashAlgorithm,
+    /// 下载地址和更新时间，与 releases.json 中不一致时重新下载
+    pub url: String,
+    pub updated_at: String,
+    /// 发布版本提供了签名时的检查结果
+    pub signature: Option<Signature>,
+    /// 文件名到摘要的映射
+    pub digests: BTreeMap<String, String>,
+}
+
+impl Manifest {
+    /// 缓存的校验文件是否与 releases.json 中的文件一致
+    fn is_fresh(&self, asset: &Asset, url: &str) -> bool {
+        self.name == asset.name && self.url == url && self.updated_at == asset.updated_at
+    }
+
+    /// 查找文件的摘要，文件名完整匹配
+    pub fn get(&self, file_name: &str) -> Option<Digest> {
+        self.digests.get(file_name).map(|value| Digest {
+            algorithm: self.algorithm,
+            value: value.clone(),
+        })
+    }
+}
+
+/// 查找发布版本中的校验文件，优先使用 SHA-512
+fn find_sums_asset(assets: &[Asset]) -> Option<(HashAlgorithm, &Asset)> {
+    HashAlgorithm::ALL.iter().find_map(|algorithm| {
+        assets
+            .iter()
+            .find(|a| a.name.starts_with(algorithm.sums_prefix()) && a.name.ends_with(".txt"))
+            .map(|a| (*algorithm, a))
+    })
+}
+
+/// 查找校验文件的分离签名，如 SHA512-SUMS.txt.asc
+fn find_signature_asset<'a>(assets: &'a [Asset], sums: &Asset) -> Option<&'a Asset> {
+    [".asc", ".sig"].iter().find_map(|ext| {
+        assets
+            .iter()
+            .find(|a| a.name == format!("{}{}", sums.name, ext))
+    })
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .unwrap_or_default()
+        .to_string_lossy()
+        .to_string()
+}
+
+/// 使用 gpg 检查校验文件的签名
+///
+/// 没有安装 gpg 或缺少公钥时无法判断签名是否有效，返回 `Signature::Unchecked`。
+fn verify_signature(sums: &Path, signature: &Path) -> Result<Signature, GdemError> {
+    let output = match Command::new("gpg")
+        .arg("--batch")
+        .arg("--verify")
+        .arg(signature)
+        .arg(sums)
+        .output()
+    {
+        Ok(output) => output,
+        Err(_) => return Ok(Signature::Unchecked("gpg not found".to_string())),
+    };
+    // gpg 签名错误时返回 1，缺少公钥等其他问题返回 2
+    match output.status.code() {
+        Some(0) => Ok(Signature::Verified),
+        Some(1) => Err(GdemError::SignatureInvalid(format!(
+            "{} does not match {}",
+            file_name(signature),
+            file_name(sums)
+        ))),
+        _ => {
+            let stderr = String::from_utf8_lossy(&output.stderr);
+            let reason = stderr.lines().last().unwrap_or("gpg failed").trim();
+            Ok(Signature::Unchecked(reason.to_string()))
+        }
+    }
+}
+
+/// 获取文件所属发布版本的校验文件
+///
+/// 解析后的校验文件缓存在 `cache/<major>/<version>/checksums.json` 中，
+/// releases.json 中校验文件的地址或更新时间变化后重新下载。
+/// 发布版本提供了分离签名时一并下载，并使用 gpg 检查。
+///
+/// # Arguments
+///
+/// * `file_name` - 发布版本中的文件名
+/// * `cfg` - 配置
+pub async fn load_manifest(file_name: &str, cfg: &Config) -> Result<Manifest, GdemError> {
+    let assets = load_remote_engine_assets(file_name, &cfg.data)?;
+    let (algorithm, asset) = find_sums_asset(&assets).ok_or_else(|| {
+        GdemError::NotFound(format!(
+            "No SHA512-SUMS or SHA256-SUMS found for {}, use -k to skip the check",
+            file_name
+        ))
+    })?;
+    let url = format_url(&asset.browser_download_url, Some(cfg.source.clone()));
+
+    let cache_dir = get_levels_dir(&cfg.cache, file_name)?;
+    let manifest_path = cache_dir.join(MANIFEST_FILE);
+    let cached = load_json(&manifest_path)
+        .ok()
+        .and_then(|json| serde_json::from_value::<Manifest>(json).ok());
+    if let Some(manifest) = cached.filter(|m| m.is_fresh(asset, &url)) {
+        return Ok(manifest);
+    }
+
+    // 校验文件可能已更新，重新下载
+    let options = cfg.download_options();
+    let sums_path = cache_dir.join(&asset.name);
+    if sums_path.exists() {
+        fs::remove_file(&sums_path)?;
+    }
+    download_file(&url, &sums_path, &options).await?;
+    let digests = parse_sums(&fs::read_to_string(&sums_path)?, algorithm);
+
+    let signature = match find_signature_asset(&assets, asset) {
+        Some(sig_asset) => {
+            let sig_url = format_url(&sig_asset.browser_download_url, Some(cfg.source.clone()));
+            let sig_path = cache_dir.join(&sig_asset.name);
+            if sig_path.exists() {
+                fs::remove_file(&sig_path)?;
+            }
+            download_file(&sig_url, &sig_path, &options).await?;
+            let signature = verify_signature(&sums_path, &sig_path);
+            fs::remove_file(&sig_path)?;
+            Some(signature?)
+        }
+        None => None,
+    };
+    fs::remove_file(&sums_path)?;
+
+    let manifest = Manifest {
+        name: asset.name.clone(),
+        algorithm,
+        url,
+        updated_at: asset.updated_at.clone(),
+        signature,
+        digests,
+    };
+    save_json(&serde_json::to_value(&manifest)?, &manifest_path)?;
+    Ok(manifest)
+}
+
+/// 获取文件期望的摘要
+pub async fn get_remote_digest(file_name: &str, cfg: &Config) -> Result<Digest, GdemError> {
+    let manifest = load_manifest(file_name, cfg).await?;
+    manifest.get(file_name).ok_or_else(|| {
+        GdemError::NotFound(format!(
+            "{} is not listed in {}, use -k to skip the check",
+            file_name, manifest.name
+        ))
+    })
+}
+
+/// 校验已下载的文件，不一致时将文件移动到 `cache/.quarantine` 并返回错误
+pub async fn check_file(path: &Path, digest: &Digest, cfg: &Config) -> Result<(), GdemError> {
+    let actual = digest.algorithm.file_digest(path)?;
+    if actual.eq_ignore_ascii_case(&digest.value) {
+        return Ok(());
+    }
+    let name = file_name(path);
+    let dir = cfg.download_options().quarantine;
+    let quarantine = quarantine_file(path, &name, dir.as_deref()).await?;
+    Err(GdemError::ChecksumMismatch {
+        file: name,
+        expected: digest.value.clone(),
+        actual,
+        quarantine,
+    })
+}
+
+/// 缓存中一个归档的校验结果
+#[derive(Debug, Clone)]
+pub struct ArchiveCheck {
+    pub entry: CacheEntry,
+    /// 期望的摘要，校验文件中没有该文件时为 None
+    pub expected: Option<Digest>,
+    /// 实际的摘要
+    pub actual: Option<String>,
+    pub signature: Option<Signature>,
+}
+
+impl ArchiveCheck {
+    /// 摘要一致，没有期望的摘要时为 None
+    pub fn is_ok(&self) -> Option<bool> {
+        let expected = self.expected.as_ref()?;
+        let actual = self.actual.as_deref()?;
+        Some(expected.value.eq_ignore_ascii_case(actual))
+    }
+}
+
+/// 重新校验缓存中已下载完成的归档
+///
+/// 校验失败的文件不会被移动，只报告结果。
+///
+/// # Arguments
+///
+/// * `engine` - 只校验该文件或引擎，如 Godot_v4.4.1-stable_win64.exe.zip 或 Godot_v4.4.1-stable_win64，为 None 时校验所有归档
+/// * `cfg` - 配置
+pub async fn verify_cache(
+    engine: Option<&str>,
+    cfg: &Config,
+) -> Result<Vec<ArchiveCheck>, GdemError> {
+    let entries: Vec<CacheEntry> = list_cache(cfg)?
+        .into_iter()
+        .filter(|e| e.is_archive() && !e.partial)
+        .filter(|e| engine.is_none_or(|q| e.name == q || format_engine_name(&e.name) == q))
+        .collect();
+    if let Some(engine) = engine
+        && entries.is_empty()
+    {
+        Err(GdemError::NotFound(format!(
+            "{} is not in the cache, run `gdem cache ls` to see the cached files",
+            engine
+        )))?
+    }
+
+    let mut checks = Vec::new();
+    for entry in entries {
+        let manifest = match load_manifest(&entry.name, cfg).await {
+            Ok(manifest) => Some(manifest),
+            Err(GdemError::NotFound(_)) => None,
+            Err(e) => return Err(e),
+        };
+        let expected = manifest.as_ref().and_then(|m| m.get(&entry.name));
+        let actual = match &expected {
+            Some(digest) => Some(digest.algorithm.file_digest(&entry.path)?),
+            None => None,
+        };
+        checks.push(ArchiveCheck {
+            entry,
+            expected,
+            actual,
+            signature: manifest.and_then(|m| m.signature),
+        });
+    }
+    Ok(checks)
+}
@@ -1,11 +1,20 @@
 use crate::core::config::ConfigTrait;
+use crate::core::download::{
+    DEFAULT_CONNECT_TIMEOUT, DEFAULT_CONNECTIONS, DEFAULT_READ_TIMEOUT, DEFAULT_RETRIES,
+    DEFAULT_STALL_TIMEOUT, DownloadOptions,
+};
+use crate::core::error::GdemError;
 use crate::core::source::Source;
-use crate::core::utils::{load_json, save_json, symlink};
+use crate::core::utils::{ExtractLimits, load_json, save_json, symlink};
+use crate::func::cache::QUARANTINE_DIR;
+use crate::func::data::{GodotDir, get_godot_dirs};
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
 use std::env;
 use std::fs;
 use std::io;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::time::Duration;
 
 #[derive(Clone, Serialize, Deserialize)]
 pub struct Config {
@@ -16,19 +25,35 @@ pub struct Config {
     pub proxy: String,
     pub version: String,
     pub source: Source,
+    /// 新安装的引擎默认使用自包含模式
+    pub self_contained: bool,
+    /// 是否将 Godot 的用户数据目录转移到 data 下并链接回原位置
+    pub link_data: bool,
+    /// 下载时同时使用的连接数
+    pub connections: usize,
+    /// 建立连接的超时秒数
+    pub connect_timeout: u64,
+    /// 等待响应的超时秒数
+    pub read_timeout: u64,
+    /// 下载停滞的超时秒数，超过该时间没有收到数据时重新连接
+    pub stall_timeout: u64,
+    /// 下载失败时的最大重试次数
+    pub retries: u32,
+    /// 各下载源的解压限制，键为下载源的名称，没有设置的下载源使用默认限制
+    pub extract_limits: BTreeMap<String, ExtractLimits>,
 }
 impl ConfigTrait for Config {
-    fn init() -> Self {
-        let root = Self::get_root();
+    fn init() -> Result<Self, GdemError> {
+        let root = Self::get_root()?;
         let config_file = root.join("config.json");
         // 如果已存在则load，否则创建
         let cfg = if config_file.exists() {
-            Self::load(root)
+            Self::load(root)?
         } else {
             Self::new(root)
         };
-        cfg.init_path();
-        cfg
+        cfg.init_path()?;
+        Ok(cfg)
     }
     fn new(root: PathBuf) -> Self {
         let home = root.join("home");
@@ -42,23 +67,42 @@ impl ConfigTrait for Config {
             proxy: "".to_string(),
             version: "".to_string(),
             source: Source::GodotHub,
+            self_contained: false,
+            link_data: true,
+            connections: DEFAULT_CONNECTIONS,
+            connect_timeout: DEFAULT_CONNECT_TIMEOUT,
+            read_timeout: DEFAULT_READ_TIMEOUT,
+            stall_timeout: DEFAULT_STALL_TIMEOUT,
+            retries: DEFAULT_RETRIES,
+            extract_limits: BTreeMap::new(),
         }
     }
 
-    fn get_root() -> PathBuf {
+    fn get_root() -> Result<PathBuf, GdemError> {
         let gdem_root = env::var("GDEM_ROOT");
         match gdem_root {
-            Ok(root) => PathBuf::from(root),
+            Ok(root) => Ok(PathBuf::from(root)),
             Err(_) => {
                 // 默认目录
-                let home = env::var("HOME").unwrap();
-                PathBuf::from(home).join(".gdem")
+                let home = dirs::home_dir().ok_or_else(|| {
+                    GdemError::NotFound(
+                        "Cannot find the home directory, set GDEM_ROOT instead".to_string(),
+                    )
+                })?;
+                Ok(home.join(".gdem"))
             }
         }
     }
 
-    fn load(root: PathBuf) -> Self {
-        let config = load_json(&root.join("config.json")).unwrap();
+    fn load(root: PathBuf) -> Result<Self, GdemError> {
+        let config_file = root.join("config.json");
+        let config = load_json(&config_file).map_err(|e| {
+            GdemError::ConfigCorrupt(format!(
+                "{} is corrupt ({}), fix or remove it to use the defaults",
+                config_file.display(),
+                e
+            ))
+        })?;
         let root = Self::val2path(config.get("root"));
         let home = Self::val2path(config.get("home"));
         let cache = Self::val2path(config.get("cache"));
@@ -66,38 +110,103 @@ impl ConfigTrait for Config {
         let proxy = Self::val2str(config.get("proxy"));
         let version = Self::val2str(config.get("version"));
         let source = Self::val2str(config.get("source"));
-        let source = Source::from_str(source.as_str());
-        Self {
+        let source = Source::from_name(source.as_str());
+        let self_contained = Self::val2bool(config.get("self_contained"));
+        // 旧版本的配置没有该字段，默认开启
+        let link_data = config
+            .get("link_data")
+            .and_then(|v| v.as_bool())
+            .unwrap_or(true);
+        let connections = config
+            .get("connections")
+            .and_then(|v| v.as_u64())
+            .map_or(DEFAULT_CONNECTIONS, |v| v as usize);
+        let seconds = |key: &str, default: u64| {
+            config
+                .get(key)
+                .and_then(|v| v.as_u64())
+                .unwrap_or(default)
+        };
+        let connect_timeout = seconds("connect_timeout", DEFAULT_CONNECT_TIMEOUT);
+        let read_timeout = seconds("read_timeout", DEFAULT_READ_TIMEOUT);
+        let stall_timeout = seconds("stall_timeout", DEFAULT_STALL_TIMEOUT);
+        let retries = seconds("retries", DEFAULT_RETRIES as u64) as u32;
+        let extract_limits = config
+            .get("extract_limits")
+            .and_then(|v| serde_json::from_value(v.clone()).ok())
+            .unwrap_or_default();
+        Ok(Self {
             root,
             home,
             cache,
             data,
             proxy,
             version,
-            source: source,
-        }
+            source,
+            self_contained,
+            link_data,
+            connections,
+            connect_timeout,
+            read_timeout,
+            stall_timeout,
+            retries,
+            extract_limits,
+        })
     }
-    fn init_path(&self) {
-        Self::init_dir(&[&self.root, &self.home, &self.cache, &self.data]);
+    fn init_path(&self) -> Result<(), GdemError> {
+        Self::init_dir(&[&self.root, &self.home, &self.cache, &self.data])
     }
-    fn save(&self) {
-        let config = serde_json::to_value(&self).unwrap();
-        save_json(&config, &self.root.join("config.json")).unwrap();
+    fn save(&self) -> Result<(), GdemError> {
+        let config = serde_json::to_value(self)?;
+        save_json(&config, &self.root.join("config.json"))
     }
     fn switch_version(&mut self, version: &str) {
         self.version = version.to_string();
     }
 }
 
+impl Config {
+    /// 根据代理、连接数、超时和重试设置生成下载选项
+    pub fn download_options(&self) -> DownloadOptions {
+        DownloadOptions {
+            proxy: (!self.proxy.is_empty()).then(|| self.proxy.clone()),
+            connections: self.connections,
+            checksum: None,
+            connect_timeout: Duration::from_secs(self.connect_timeout),
+            read_timeout: Duration::from_secs(self.read_timeout),
+            stall_timeout: Duration::from_secs(self.stall_timeout),
+            retries: self.retries,
+            quarantine: Some(self.cache.join(QUARANTINE_DIR)),
+            progress: None,
+        }
+    }
+
+    /// 当前下载源的解压限制
+    pub fn extract_limits(&self) -> ExtractLimits {
+        self.extract_limits
+            .get(self.source.get_name())
+            .copied()
+            .unwrap_or_default()
+    }
+
+    /// 当前下载源的解压限制，用于修改
+    pub fn extract_limits_mut(&mut self) -> &mut ExtractLimits {
+        let name = self.source.get_name().to_string();
+        self.extract_limits.entry(name).or_default()
+    }
+}
+
 /// 递归复制目录
 ///
+/// 目录中的符号链接按链接复制，保留原来的指向，不复制链接指向的内容。
+///
 /// # Arguments
 /// * `src` - 源目录路径
 /// * `dst` - 目标目录路径
 ///
 /// # Returns
 /// * `Result<(), io::Error>` - 复制结果
-fn copy_dir_recursively(src: &PathBuf, dst: &PathBuf) -> Result<(), io::Error> {
+pub fn copy_dir_recursively(src: &Path, dst: &Path) -> Result<(), io::Error> {
     if !dst.exists() {
         fs::create_dir_all(dst)?;
     }
@@ -108,7 +217,9 @@ fn copy_dir_recursively(src: &PathBuf, dst: &PathBuf) -> Result<(), io::Error> {
         let src_path = entry.path();
         let dst_path = dst.join(entry.file_name());
 
-        if file_type.is_dir() {
+        if file_type.is_symlink() {
+            copy_symlink(&src_path, &dst_path)?;
+        } else if file_type.is_dir() {
             copy_dir_recursively(&src_path, &dst_path)?;
         } else {
             fs::copy(&src_path, &dst_path)?;
@@ -118,23 +229,56 @@ fn copy_dir_recursively(src: &PathBuf, dst: &PathBuf) -> Result<(), io::Error> {
     Ok(())
 }
 
-pub fn link_appdata(data: &PathBuf) {
-    let appdata = env::var("APPDATA").unwrap();
-    let appdata = PathBuf::from(appdata);
-    let appdata = appdata.join("Godot");
-    let data_path = data.join("Godot");
+/// 复制符号链接本身，目标位置已有同名的文件或链接时先删除
+fn copy_symlink(src: &Path, dst: &Path) -> Result<(), io::Error> {
+    let target = fs::read_link(src)?;
+    if dst.symlink_metadata().is_ok_and(|m| !m.is_dir()) {
+        fs::remove_file(dst)?;
+    }
+    #[cfg(unix)]
+    {
+        std::os::unix::fs::symlink(&target, dst)
+    }
+    #[cfg(windows)]
+    {
+        // Windows 下目录和文件的链接不同，按链接指向的类型创建
+        if src.is_dir() {
+            std::os::windows::fs::symlink_dir(&target, dst)
+        } else {
+            std::os::windows::fs::symlink_file(&target, dst)
+        }
+    }
+}
 
-    if appdata.exists() {
+/// 将 Godot 的用户数据目录转移到 GDEM_ROOT/data 下，并在原位置创建链接
+///
+/// 支持 Windows、Linux 和 macOS，每个系统对应的目录见 `get_godot_dirs`。
+pub fn link_appdata(data: &Path) -> Result<(), GdemError> {
+    for dir in get_godot_dirs(data)? {
+        link_godot_dir(&dir)?;
+    }
+    Ok(())
+}
+
+fn link_godot_dir(dir: &GodotDir) -> Result<(), GdemError> {
+    let appdata = &dir.system;
+    let data_path = &dir.managed;
+
+    if appdata.exists() || appdata.is_symlink() {
         // 使用复制和删除替代重命名，以支持跨磁盘移动
         if !appdata.is_symlink() {
-            copy_dir_recursively(&appdata, &data_path).unwrap();
-            std::fs::remove_dir_all(&appdata).unwrap();
+            copy_dir_recursively(appdata, data_path)?;
+            std::fs::remove_dir_all(appdata)?;
         } else {
-            return;
+            return Ok(());
         }
     }
     if !data_path.exists() {
-        std::fs::create_dir_all(&data_path).unwrap();
+        std::fs::create_dir_all(data_path)?;
+    }
+    if let Some(parent) = appdata.parent() {
+        std::fs::create_dir_all(parent)?;
     }
-    symlink(&data_path, &appdata).unwrap();
+    symlink(data_path, appdata)?;
+    Ok(())
 }
@@ -0,0 +1,433 @@
+use crate::core::error::GdemError;
+use crate::core::utils::{
+    ExtractLimits, create_tar_gz, dir_size, dir_stats, extract_tar_gz, list_tar_gz, symlink,
+};
+use crate::func::config::copy_dir_recursively;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// Godot 的一个用户数据目录，以及它在 GDEM_ROOT/data 下对应的位置
+#[derive(Debug, Clone)]
+pub struct GodotDir {
+    /// 目录类型：data、config 或 cache
+    pub kind: &'static str,
+    /// Godot 使用的系统目录
+    pub system: PathBuf,
+    /// 转移到 GDEM_ROOT/data 下的目录
+    pub managed: PathBuf,
+}
+
+/// 目录当前的状态
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DirState {
+    /// 系统目录是指向 GDEM_ROOT/data 的链接
+    Linked,
+    /// 系统目录是指向其他位置的链接
+    LinkedElsewhere(PathBuf),
+    /// 系统目录是普通目录，尚未转移
+    Local,
+    /// 系统目录不存在
+    Missing,
+}
+
+impl DirState {
+    pub fn get_labels(&self) -> String {
+        match self {
+            Self::Linked => "linked".to_string(),
+            Self::LinkedElsewhere(target) => format!("linked to {}", target.display()),
+            Self::Local => "local".to_string(),
+            Self::Missing => "missing".to_string(),
+        }
+    }
+}
+
+/// 获取当前系统下 Godot 的用户数据目录
+///
+/// * Windows: `%APPDATA%\Godot`
+/// * macOS: `~/Library/Application Support/Godot`
+/// * Linux: `$XDG_DATA_HOME/godot`、`$XDG_CONFIG_HOME/godot` 和 `$XDG_CACHE_HOME/godot`
+///
+/// data 目录统一转移到 `data/Godot`，导出模板因此始终位于 `data/Godot/export_templates`。
+pub fn get_godot_dirs(data: &Path) -> Result<Vec<GodotDir>, GdemError> {
+    let data_dir = dirs::data_dir().ok_or_else(|| dir_not_found("data"))?;
+    let mut godot_dirs = Vec::new();
+
+    if cfg!(target_os = "linux") {
+        let config_dir = dirs::config_dir().ok_or_else(|| dir_not_found("config"))?;
+        let cache_dir = dirs::cache_dir().ok_or_else(|| dir_not_found("cache"))?;
+        godot_dirs.push(GodotDir {
+            kind: "data",
+            system: data_dir.join("godot"),
+            managed: data.join("Godot"),
+        });
+        godot_dirs.push(GodotDir {
+            kind: "config",
+            system: config_dir.join("godot"),
+            managed: data.join("config").join("godot"),
+        });
+        godot_dirs.push(GodotDir {
+            kind: "cache",
+            system: cache_dir.join("godot"),
+            managed: data.join("cache").join("godot"),
+        });
+    } else {
+        // Windows 的 APPDATA 与 macOS 的 Application Support 中，数据和配置在同一个目录
+        godot_dirs.push(GodotDir {
+            kind: "data",
+            system: data_dir.join("Godot"),
+            managed: data.join("Godot"),
+        });
+    }
+    Ok(godot_dirs)
+}
+
+/// 系统没有提供对应的用户目录时的错误
+fn dir_not_found(kind: &str) -> GdemError {
+    GdemError::NotFound(format!("Cannot find the user {} directory", kind))
+}
+
+/// 获取目录当前的状态
+pub fn get_dir_state(dir: &GodotDir) -> DirState {
+    if dir.system.is_symlink() {
+        match fs::read_link(&dir.system) {
+            Ok(target) if target == dir.managed => DirState::Linked,
+            Ok(target) => DirState::LinkedElsewhere(target),
+            Err(_) => DirState::Missing,
+        }
+    } else if dir.system.exists() {
+        DirState::Local
+    } else {
+        DirState::Missing
+    }
+}
+
+/// Godot 用户数据目录的状态
+#[derive(Debug, Clone)]
+pub struct DirStatus {
+    pub dir: GodotDir,
+    pub state: DirState,
+    /// 数据实际所在的目录
+    pub location: Option<PathBuf>,
+    pub size: u64,
+}
+
+/// 查看每个 Godot 用户数据目录当前所在的位置
+pub fn data_status(data: &Path) -> Result<Vec<DirStatus>, GdemError> {
+    let mut status = Vec::new();
+    for dir in get_godot_dirs(data)? {
+        let state = get_dir_state(&dir);
+        let location = match &state {
+            DirState::Linked => Some(dir.managed.clone()),
+            DirState::LinkedElsewhere(target) => Some(target.clone()),
+            DirState::Local => Some(dir.system.clone()),
+            DirState::Missing => None,
+        };
+        let size = location
+            .as_deref()
+            .and_then(|path| dir_size(path).ok())
+            .unwrap_or(0);
+        status.push(DirStatus {
+            dir,
+            state,
+            location,
+            size,
+        });
+    }
+    Ok(status)
+}
+
+/// 撤销数据目录的转移：删除系统目录处的链接，并将数据复制回原位置
+///
+/// 复制完成后会校验文件数量和大小。任何一个目录失败时，已撤销的目录会恢复链接，
+/// 磁盘上的状态与配置中的 `link_data` 保持一致。GDEM_ROOT/data 中的数据会保留。
+///
+/// # Returns
+///
+/// * `Result<Vec<GodotDir>, GdemError>` - 已撤销转移的目录
+pub fn unlink_data(data: &Path) -> Result<Vec<GodotDir>, GdemError> {
+    let mut unlinked: Vec<GodotDir> = Vec::new();
+    for dir in get_godot_dirs(data)? {
+        if get_dir_state(&dir) != DirState::Linked {
+            continue;
+        }
+        if let Err(e) = unlink_dir(&dir) {
+            // 恢复之前已撤销的目录，它们的副本刚刚复制，GDEM_ROOT/data 中的数据仍然完整
+            for done in unlinked.iter().rev() {
+                relink_dir(done)?;
+            }
+            return Err(e);
+        }
+        unlinked.push(dir);
+    }
+    Ok(unlinked)
+}
+
+/// 撤销一个目录的转移，失败时删除不完整的副本并恢复链接
+fn unlink_dir(dir: &GodotDir) -> Result<(), GdemError> {
+    fs::remove_file(&dir.system).or_else(|_| fs::remove_dir(&dir.system))?;
+    let copied = copy_dir_recursively(&dir.managed, &dir.system)
+        .map_err(|e| e.into())
+        .and_then(|_| verify_copy(&dir.managed, &dir.system));
+    if let Err(e) = copied {
+        relink_dir(dir)?;
+        return Err(GdemError::Io(io::Error::other(format!(
+            "Unlink {} failed: {}",
+            dir.system.display(),
+            e
+        ))));
+    }
+    Ok(())
+}
+
+/// 删除系统目录处的副本，重新创建指向 GDEM_ROOT/data 的链接
+fn relink_dir(dir: &GodotDir) -> Result<(), GdemError> {
+    if dir.system.symlink_metadata().is_ok_and(|m| m.is_dir()) {
+        fs::remove_dir_all(&dir.system)?;
+    }
+    symlink(&dir.managed, &dir.system)
+}
+
+fn verify_copy(src: &Path, dst: &Path) -> Result<(), GdemError> {
+    let (src_count, src_size) = dir_stats(src)?;
+    let (dst_count, dst_size) = dir_stats(dst)?;
+    if src_count != dst_count || src_size != dst_size {
+        return Err(GdemError::Io(io::Error::other(format!(
+            "copy mismatch, expected {} files ({} bytes), got {} files ({} bytes)",
+            src_count, src_size, dst_count, dst_size
+        ))));
+    }
+    Ok(())
+}
+
+/// 备份时包含的编辑器数据，以 `*` 结尾表示前缀匹配
+const BACKUP_ITEMS: &[&str] = &[
+    "editor_settings-*",
+    "projects.cfg",
+    "script_templates",
+    "feature_profiles",
+    "text_editor_themes",
+];
+
+/// 获取目录中数据实际所在的位置，不存在时返回系统目录
+fn get_data_location(dir: &GodotDir) -> PathBuf {
+    match get_dir_state(dir) {
+        DirState::Linked => dir.managed.clone(),
+        DirState::LinkedElsewhere(target) => target,
+        DirState::Local | DirState::Missing => dir.system.clone(),
+    }
+}
+
+fn is_backup_item(name: &str, include_templates: bool) -> bool {
+    if include_templates && name == "export_templates" {
+        return true;
+    }
+    BACKUP_ITEMS
+        .iter()
+        .any(|item| match item.strip_suffix('*') {
+            Some(prefix) => name.starts_with(prefix),
+            None => name == *item,
+        })
+}
+
+/// 备份 Godot 编辑器数据，包括编辑器设置、项目列表和脚本模板，可选导出模板
+///
+/// 压缩包中的路径为 `<kind>/<name>`，如 `config/projects.cfg`，缓存目录不会备份。
+///
+/// # Arguments
+///
+/// * `data` - GDEM_ROOT/data 目录
+/// * `backup_dir` - 保存备份文件的目录
+/// * `include_templates` - 是否包含导出模板
+///
+/// # Returns
+///
+/// * `Result<(PathBuf, Vec<String>), GdemError>` - 备份文件路径以及备份的条目
+pub fn backup_data(
+    data: &Path,
+    backup_dir: &Path,
+    include_templates: bool,
+) -> Result<(PathBuf, Vec<String>), GdemError> {
+    let mut entries: Vec<(PathBuf, String)> = Vec::new();
+    for dir in get_godot_dirs(data)? {
+        if dir.kind == "cache" {
+            continue;
+        }
+        let location = get_data_location(&dir);
+        let items = match location.read_dir() {
+            Ok(items) => items,
+            Err(_) => continue,
+        };
+        for item in items {
+            let item = item?;
+            let name = item.file_name().to_string_lossy().to_string();
+            if is_backup_item(&name, include_templates) {
+                entries.push((item.path(), format!("{}/{}", dir.kind, name)));
+            }
+        }
+    }
+    if entries.is_empty() {
+        Err(GdemError::NotFound("No Godot editor data found".to_string()))?
+    }
+    entries.sort_by(|a, b| a.1.cmp(&b.1));
+
+    let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+    let backup_file = backup_dir.join(format!("godot-data-{}.tar.gz", timestamp));
+    create_tar_gz(&entries, &backup_file)?;
+    let names = entries.into_iter().map(|(_, name)| name).collect();
+    Ok((backup_file, names))
+}
+
+/// 恢复时将要写入的一项数据
+#[derive(Debug, Clone)]
+pub struct RestoreItem {
+    /// 压缩包中的路径，如 config/projects.cfg
+    pub name: String,
+    pub target: PathBuf,
+    /// 目标已存在，恢复时会被替换
+    pub exists: bool,
+}
+
+/// 将备份中的条目分为目录类型和数据项的名称，如 `config/projects.cfg/...` 分为 `config` 和 `projects.cfg`
+///
+/// 数据项会替换数据目录中的同名项，名称为空或 `.` 时会替换整个数据目录，因此拒绝这样的条目。
+fn split_backup_entry(entry: &str) -> Result<(&str, &str), GdemError> {
+    let mut parts = entry.splitn(3, '/');
+    match (parts.next(), parts.next()) {
+        (Some(kind), Some(item_name))
+            if !matches!(item_name, "" | "." | "..") && !item_name.contains('\\') =>
+        {
+            Ok((kind, item_name))
+        }
+        _ => Err(GdemError::UnsafeArchive(format!(
+            "{}: not a data item in the backup",
+            entry
+        ))),
+    }
+}
+
+/// 预览恢复备份时会写入和替换的数据
+pub fn preview_restore(archive: &Path, data: &Path) -> Result<Vec<RestoreItem>, GdemError> {
+    let godot_dirs = get_godot_dirs(data)?;
+    let mut items: Vec<RestoreItem> = Vec::new();
+    for entry in list_tar_gz(archive)? {
+        let (kind, item_name) = split_backup_entry(&entry)?;
+        let name = format!("{}/{}", kind, item_name);
+        if items.iter().any(|i| i.name == name) {
+            continue;
+        }
+        let dir = godot_dirs
+            .iter()
+            .find(|d| d.kind == kind)
+            .ok_or_else(|| {
+                GdemError::InvalidInput(format!("Unknown data directory in backup: {}", kind))
+            })?;
+        let target = get_data_location(dir).join(item_name);
+        items.push(RestoreItem {
+            name,
+            exists: target.exists(),
+            target,
+        });
+    }
+    if items.is_empty() {
+        Err(GdemError::InvalidInput("Backup archive is empty".to_string()))?
+    }
+    Ok(items)
+}
+
+/// 从备份文件恢复 Godot 编辑器数据，已存在的同名数据会被替换
+///
+/// 备份先解压到 `.restore` 临时目录，已存在的数据先移动到旁边的隐藏目录，
+/// 任何一项复制失败时删除已恢复的数据并移回原来的数据。临时目录在返回前删除。
+pub fn restore_data(archive: &Path, data: &Path) -> Result<Vec<RestoreItem>, GdemError> {
+    let items = preview_restore(archive, data)?;
+
+    let staging = data.join(".restore");
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    // 备份由用户自己创建，使用默认的限制
+    let result = extract_tar_gz(archive, &staging, &ExtractLimits::default())
+        .and_then(|_| replace_items(&items, &staging));
+    let cleanup = if staging.exists() {
+        fs::remove_dir_all(&staging)
+    } else {
+        Ok(())
+    };
+    result?;
+    cleanup?;
+    Ok(items)
+}
+
+/// 恢复时原来的数据移动到的位置，如 config/godot/.projects.cfg.restore-backup
+fn restore_backup_path(target: &Path) -> PathBuf {
+    let name = target.file_name().unwrap_or_default().to_string_lossy();
+    target.with_file_name(format!(".{}.restore-backup", name))
+}
+
+/// 删除文件、链接或目录，不存在时忽略
+fn remove_path(path: &Path) -> io::Result<()> {
+    match path.symlink_metadata() {
+        Ok(meta) if meta.is_dir() => fs::remove_dir_all(path),
+        Ok(_) => fs::remove_file(path),
+        Err(_) => Ok(()),
+    }
+}
+
+/// 用临时目录中的数据替换每一项，失败时恢复已替换的项
+fn replace_items(items: &[RestoreItem], staging: &Path) -> Result<(), GdemError> {
+    // (恢复的位置, 原来的数据移动到的位置)
+    let mut replaced: Vec<(&Path, Option<PathBuf>)> = Vec::new();
+    let result = items
+        .iter()
+        .try_for_each(|item| replace_item(item, staging, &mut replaced));
+    if let Err(e) = result {
+        for (target, backup) in replaced.into_iter().rev() {
+            remove_path(target)?;
+            if let Some(backup) = backup {
+                fs::rename(backup, target)?;
+            }
+        }
+        return Err(e);
+    }
+    for (_, backup) in replaced {
+        if let Some(backup) = backup {
+            remove_path(&backup)?;
+        }
+    }
+    Ok(())
+}
+
+fn replace_item<'a>(
+    item: &'a RestoreItem,
+    staging: &Path,
+    replaced: &mut Vec<(&'a Path, Option<PathBuf>)>,
+) -> Result<(), GdemError> {
+    let target = item.target.as_path();
+    let backup = restore_backup_path(target);
+    // 上次恢复中断时，备份是唯一完整的数据
+    if backup.symlink_metadata().is_ok() {
+        if target.symlink_metadata().is_ok() {
+            remove_path(&backup)?;
+        } else {
+            fs::rename(&backup, target)?;
+        }
+    }
+    if target.symlink_metadata().is_ok() {
+        fs::rename(target, &backup)?;
+        replaced.push((target, Some(backup)));
+    } else {
+        replaced.push((target, None));
+    }
+
+    let src = staging.join(&item.name);
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    if src.is_dir() {
+        copy_dir_recursively(&src, target)?;
+    } else {
+        fs::copy(&src, target)?;
+    }
+    Ok(())
+}
@@ -1,238 +1,420 @@
+use crate::core::download::{DownloadOptions, download_file};
+use crate::core::error::GdemError;
+use crate::core::hash::HashAlgorithm;
 use crate::core::source::format_url;
-use crate::core::style::new_spinner;
-use crate::core::utils::{download_file, extract_zip, sha512sum};
+use crate::core::style::{new_multi_progress, new_spinner};
+use crate::core::utils::{ExtractLimits, ExtractReport, extract_zip, set_executable};
+use crate::func::checksum::{Digest, check_file, get_remote_digest};
 use crate::func::config::Config;
+use crate::func::preflight::preflight;
+use crate::func::templates::{get_template_install_roots, query_template_asset};
 use crate::func::tool::{
-    format_engine_name, get_asset_name, get_levels_dir, load_remote_engine_assets,
+    extract_tag, find_engine_executable, format_engine_name, get_asset_name,
+    get_editor_data_dir, get_levels_dir, get_levels_path, get_tpz_template_dir,
+    is_self_contained, load_remote_engine_assets, set_self_contained,
 };
-use std::error::Error;
+use indicatif::{MultiProgress, ProgressBar};
 use std::fs;
-use std::path::Path;
+use std::path::{Path, PathBuf};
 use tokio::fs::remove_file;
 
-fn query_url(file_name: &str, data: &Path) -> Result<String, Box<dyn Error>> {
+fn query_url(file_name: &str, data: &Path) -> Result<String, GdemError> {
     let assets = load_remote_engine_assets(file_name, data)?;
 
     // 查找文件名
-    let asset = assets.flitter(|v| {
-        let name = v["name"].as_str().unwrap();
-        if name.contains(file_name) {
-            return true;
-        }
-        false
-    })?;
-    let asset = asset.document.first().unwrap();
-
-    let url = asset["browser_download_url"].as_str().unwrap().to_string();
-
-    Ok(url)
-}
-fn query_sum_file_url(file_name: &str, data: &Path) -> Result<String, Box<dyn Error>> {
-    let assets = load_remote_engine_assets(file_name, data)?;
-    let asset = assets.flitter(|v| {
-        let name = v["name"].as_str().unwrap();
-        if name.contains("SHA512-SUMS") {
-            return true;
-        }
-        false
-    })?;
-    let asset = asset.document.first().unwrap();
-    let url = asset["browser_download_url"].as_str().unwrap().to_string();
-
-    Ok(url)
-}
-
-async fn get_remote_sha512(
-    file_name: &str,
-    cfg: &Config,
-    proxy_url: Option<&str>,
-) -> Result<String, Box<dyn Error>> {
-    let cache_dir = get_levels_dir(&cfg.cache, file_name);
-    let sum_url = query_sum_file_url(file_name, &cfg.data).unwrap();
-    let source = cfg.source.clone();
-    let sum_url = format_url(sum_url.as_str(), Some(source));
-    // sum_file_path
-    let sum_file_path = cache_dir.join("SHA512-SUMS.txt");
-    // 如果sum_file_path 不存在
-    if !sum_file_path.exists() {
-        match download_file(sum_url.as_str(), &sum_file_path.as_path(), proxy_url).await {
-            Ok(_) => {}
-            Err(e) => {
-                return Err(e);
-            }
-        }
-    }
-    // 读取sum_file_path
-    let sum_text = fs::read_to_string(&sum_file_path)?;
-
-    // 从sum_text中查找对应文件名的sha512值
-    for line in sum_text.lines() {
-        // 每行格式为: <sha512_hash>  <file_name>
-        if let Some(pos) = line.find(file_name) {
-            // 提取sha512值
-            let sha512 = line[..pos].trim();
-            return Ok(sha512.to_string());
-        }
-    }
-
-    Err(format!("SHA512 for {} not found", file_name).into())
+    let asset = assets
+        .iter()
+        .find(|a| a.name == file_name)
+        .ok_or_else(|| GdemError::NotFound(format!("{} not found", file_name)))?;
+    Ok(asset.browser_download_url.clone())
 }
 
-async fn check_sha512(
+async fn install_engine(
     file_name: &str,
     cfg: &Config,
-    proxy_url: Option<&str>,
-) -> Result<bool, Box<dyn Error>> {
-    let cache_dir = get_levels_dir(&cfg.cache, file_name);
-    let remote_sha512 = get_remote_sha512(file_name, cfg, proxy_url).await?;
-    let local_sha512 = sha512sum(cache_dir.join(file_name))?;
-    Ok(remote_sha512 == local_sha512)
-}
-
-async fn install_engine(file_name: &str, cfg: &Config) -> Result<String, Box<dyn Error>> {
-    let cache_dir = get_levels_dir(&cfg.cache, file_name);
+    digest: Option<&Digest>,
+    progress: &MultiProgress,
+) -> Result<String, GdemError> {
+    let cache_dir = get_levels_dir(&cfg.cache, file_name)?;
     let file_path = cache_dir.join(file_name);
 
     // 获取下载链接
-    let url = query_url(file_name, &cfg.data).unwrap();
+    let url = query_url(file_name, &cfg.data)?;
     let source = cfg.source.clone();
     let url = format_url(url.as_str(), Some(source));
     // 下载路径
-    let proxy_url = if cfg.proxy.is_empty() {
-        None
-    } else {
-        Some(cfg.proxy.as_str())
+    // SHA-512 在下载时计算，并记录在下载进度中，续传前用于判断远程文件是否已更新
+    let checksum = digest
+        .filter(|d| d.algorithm == HashAlgorithm::Sha512)
+        .map(|d| d.value.clone());
+    let options = DownloadOptions {
+        checksum,
+        progress: Some(progress.clone()),
+        ..cfg.download_options()
     };
-    match download_file(url.as_str(), &file_path.as_path(), proxy_url).await {
-        Ok(msg) => Ok(format!("{} {}", file_name, msg)),
-        Err(e) => Err(e),
+    let msg = download_file(url.as_str(), file_path.as_path(), &options).await?;
+    // 其他算法在下载完成后校验
+    if let Some(digest) = digest.filter(|d| d.algorithm != HashAlgorithm::Sha512) {
+        check_file(&file_path, digest, cfg).await?;
     }
+    Ok(format!("{} {}", file_name, msg))
+}
+
+/// 安装选项
+#[derive(Debug, Default, Clone)]
+pub struct InstallOptions {
+    /// 删除已缓存的文件并重新下载
+    pub force: bool,
+    /// 跳过 sha512 校验
+    pub skip_check: bool,
+    /// 以自包含模式安装引擎
+    pub self_contained: bool,
+    /// 校验失败时自动重新下载一次
+    pub redownload: bool,
 }
 
 /// 完整的引擎安装流程，包括下载和校验
 ///
 /// 该函数执行完整的引擎安装流程，包括下载指定的引擎文件并校验其完整性。
-/// 如果校验失败，会自动删除已下载的文件。
+/// 下载前检查 GDEM_ROOT 的写入权限、符号链接和磁盘空间，空间不足时返回 `InsufficientSpace` 错误。
+/// 如果校验失败，已下载的文件会被移动到 `cache/.quarantine`，并返回 `ChecksumMismatch` 错误，
+/// 设置了 `redownload` 时会先重新下载一次。
 ///
 /// # Arguments
 ///
 /// * `file_name` - 要安装的引擎文件名
 /// * `cfg` - 配置对象，包含安装所需的配置信息
+/// * `options` - 安装选项
 ///
 /// # Returns
 ///
-/// * `Result<(), Box<dyn Error>>` - 成功时返回Ok(())，失败时返回错误信息
+/// * `Result<String, GdemError>` - 成功时返回安装的引擎名，失败时返回错误信息
 ///
 /// # Examples
 ///
-/// ```
-/// use gdem::func::install::full_install_process;
+/// ```no_run
+/// use gdem::core::config::ConfigTrait;
+/// use gdem::core::error::GdemError;
+/// use gdem::func::install::{InstallOptions, full_install_process};
 /// use gdem::func::config::Config;
 ///
 /// #[tokio::main]
-/// async fn main() -> Result<(), Box<dyn std::error::Error>> {
+/// async fn main() -> Result<(), GdemError> {
 ///     let engine = "Godot_v4.4.1-stable_win64.exe.zip";
-///     let cfg = Config::load()?;
-///     full_install_process(engine, &cfg).await?;
+///     let cfg = Config::init()?;
+///     full_install_process(engine, &cfg, &InstallOptions::default()).await?;
 ///     Ok(())
 /// }
 /// ```
 pub async fn full_install_process(
     engine: &str,
     cfg: &Config,
-    force: bool,
-    skip_check: bool,
-) -> Result<String, Box<dyn Error>> {
-    let proxy_url = if cfg.proxy.is_empty() {
-        None
-    } else {
-        Some(cfg.proxy.as_str())
-    };
-    let engine = &get_asset_name(&engine, &cfg.data);
+    options: &InstallOptions,
+) -> Result<String, GdemError> {
+    let engine = &get_asset_name(engine, &cfg.data)?;
+    remove_cached_if_forced(engine, cfg, options).await?;
+    // 下载前检查磁盘空间和权限，避免下载或解压到一半时失败
+    preflight(std::slice::from_ref(engine), cfg)?;
+    let digest = query_digest(engine, cfg, options).await?;
 
-    let cache_dir = get_levels_dir(&cfg.cache, engine);
-    let file_path = cache_dir.join(engine);
-    let file_name = format_engine_name(engine);
-    if file_path.exists() {
-        if force {
-            remove_file(&file_path).await?;
-        }
-    }
-
-    // 获取下载链接
-    let pb = new_spinner();
+    let progress = new_multi_progress();
+    let pb = progress.add(new_spinner());
     // 下载引擎
     pb.set_message("Downloading");
-    let msg = install_engine(engine, cfg).await.unwrap();
+    let msg = download_asset(engine, cfg, digest.as_ref(), options, &progress).await?;
     pb.finish_with_message(msg);
 
-    // 检查sum
-    if !skip_check {
-        let pb = new_spinner();
-        pb.set_message("Checking sum");
-        let check = check_sha512(engine, cfg, proxy_url).await?;
-        if !check {
-            let cache_dir = get_levels_dir(&cfg.cache, engine);
-            let file_path = cache_dir.join(engine);
-            remove_file(file_path).await?;
-            pb.finish_with_message("Checksum failed, file removed");
+    // 下载时已校验，不一致时会返回错误
+    if let Some(digest) = &digest {
+        new_spinner().finish_with_message(format!("{} checksum passed", digest.algorithm));
+    }
+
+    extract_asset(engine, cfg, options)?;
+    Ok(format_engine_name(engine))
+}
+
+/// 设置了 `force` 时删除已缓存的文件
+///
+/// 只计算路径而不创建目录，GDEM_ROOT 不可写时由安装前的检查报告。
+pub async fn remove_cached_if_forced(
+    engine: &str,
+    cfg: &Config,
+    options: &InstallOptions,
+) -> Result<(), GdemError> {
+    let file_path = get_levels_path(&cfg.cache, engine)?.join(engine);
+    if file_path.exists() && options.force {
+        remove_file(&file_path).await?;
+    }
+    Ok(())
+}
+
+/// 获取文件期望的摘要，跳过校验时为 None
+pub async fn query_digest(
+    engine: &str,
+    cfg: &Config,
+    options: &InstallOptions,
+) -> Result<Option<Digest>, GdemError> {
+    if options.skip_check {
+        return Ok(None);
+    }
+    Ok(Some(get_remote_digest(engine, cfg).await?))
+}
+
+/// 下载并校验文件，校验失败且设置了 `redownload` 时重新下载一次
+///
+/// # Arguments
+///
+/// * `engine` - 完整的文件名
+/// * `cfg` - 配置
+/// * `digest` - 期望的摘要
+/// * `options` - 安装选项
+/// * `progress` - 下载进度条所在的进度显示，同时下载多个文件时共享
+pub async fn download_asset(
+    engine: &str,
+    cfg: &Config,
+    digest: Option<&Digest>,
+    options: &InstallOptions,
+    progress: &MultiProgress,
+) -> Result<String, GdemError> {
+    match install_engine(engine, cfg, digest, progress).await {
+        Err(GdemError::ChecksumMismatch { quarantine, .. }) if options.redownload => {
+            let note = match quarantine {
+                Some(path) => format!(
+                    "Checksum mismatch for {}, moved to {}, downloading again",
+                    engine,
+                    path.display()
+                ),
+                None => format!("Checksum mismatch for {}, downloading again", engine),
+            };
+            let _ = progress.println(note);
+            install_engine(engine, cfg, digest, progress).await
         }
-        pb.finish_with_message("Checksum passed");
+        result => result,
     }
+}
 
+/// 解压已下载的引擎或导出模板到安装目录
+pub fn extract_asset(
+    engine: &str,
+    cfg: &Config,
+    options: &InstallOptions,
+) -> Result<(), GdemError> {
+    let file_path = get_levels_path(&cfg.cache, engine)?.join(engine);
     // zip 或 tpz 解压，不同的处理方式。如果要解压，要提供 spinner
-    if file_path.to_string_lossy().ends_with(".zip") {
+    if engine.ends_with(".zip") {
         let pd = new_spinner();
-        pd.set_message("Extracting");
-        extract_engine(&file_path, engine, &cfg.home)?;
+        pd.set_message(format!("Extracting {}", engine));
+        let report = extract_engine(&file_path, engine, cfg, options.self_contained)?;
+        report_skipped(&pd, &report);
         pd.finish_with_message("Extracting done");
-    } else if file_path.to_string_lossy().ends_with(".tpz") {
+    } else if engine.ends_with(".tpz") {
         let pd = new_spinner();
-        pd.set_message("Extracting");
-        extract_template(&file_path, &cfg.data, engine)?;
+        pd.set_message(format!("Extracting {}", engine));
+        // 安装目录和匹配的引擎都使用同一个目录名
+        let template_dir = get_tpz_template_dir(&file_path, engine)?;
+        for target_path in get_template_install_roots(&template_dir, cfg)? {
+            let target_folder = target_path.join(&template_dir);
+            let report =
+                extract_template(&file_path, &target_folder, engine, &cfg.extract_limits())?;
+            report_skipped(&pd, &report);
+        }
         pd.finish_with_message("Extracting done");
     }
-    Ok(file_name)
+    Ok(())
 }
 
-fn extract_engine(file_path: &Path, engine: &str, home: &Path) -> Result<(), Box<dyn Error>> {
-    let file_name = format_engine_name(engine);
-    let home_dir = get_levels_dir(home, engine);
-    let target_folder = home_dir.join(&file_name);
+/// 显示解压时跳过的条目
+fn report_skipped(pb: &ProgressBar, report: &ExtractReport) {
+    for (name, reason) in &report.skipped {
+        pb.println(format!("Skipped {}: {}", name, reason));
+    }
+}
 
-    // 确保目标文件夹存在
-    extract_zip(file_path, &target_folder)?;
-
-    // if self_contained {
-    //     // 在data_path中创建一个 _sc_ 空文件
-    //     let sc_file_path = data_path.join("_sc_");
-    //     fs::File::create(sc_file_path)?;
-    // } else {
-    //     // 如果存在_sc_文件，删除
-    //     let sc_file_path = data_path.join("_sc_");
-    //     if sc_file_path.exists() {
-    //         fs::remove_file(sc_file_path)?;
-    //     }
-    // }
-    Ok(())
+/// 安装与引擎版本和类型（是否为 mono）一致的导出模板
+///
+/// 导出模板与引擎使用同一个 SHA512-SUMS 文件校验，并解压到该引擎查找模板的目录：
+/// 自包含模式的引擎为 `editor_data/export_templates`，否则为共享的 `data/Godot/export_templates`。
+///
+/// # Arguments
+///
+/// * `engine` - 已安装的引擎名，如 Godot_v4.4.1-stable_mono_win64
+/// * `cfg` - 配置对象
+/// * `options` - 安装选项
+///
+/// # Returns
+///
+/// * `Result<String, GdemError>` - 成功时返回安装的导出模板名，失败时返回错误信息
+pub async fn install_engine_templates(
+    engine: &str,
+    cfg: &Config,
+    options: &InstallOptions,
+) -> Result<String, GdemError> {
+    let tag = extract_tag(engine)
+        .ok_or_else(|| GdemError::InvalidInput(format!("Version of {} not found", engine)))?;
+    let mono = engine.contains("_mono");
+    let asset = query_template_asset(&tag, mono, &cfg.data)?;
+    full_install_process(&asset, cfg, options).await
 }
 
-// TODO 解压导出模板 .tpz  使用 zip 解压。
-// FIXME 问题难点，需要通过 导出模板名 获取对应的 引擎名 来确定 self contained 模式 的路径
+/// 解压时使用的临时目录和替换时备份已有安装的目录后缀
+///
+/// 目录名以 `.` 开头，列出引擎和导出模板时会被忽略。
+const STAGING_SUFFIX: &str = ".staging";
+const BACKUP_SUFFIX: &str = ".old";
+
+/// 获取目标目录旁以 `.` 开头的同级目录，如 `Godot_v4.4.1-stable_win64` -> `.Godot_v4.4.1-stable_win64.staging`
+fn hidden_sibling(target: &Path, suffix: &str) -> PathBuf {
+    let name = target.file_name().unwrap_or_default().to_string_lossy();
+    target.with_file_name(format!(".{}{}", name, suffix))
+}
+
+/// 先解压到临时目录，检查通过后再替换目标目录
+///
+/// 任何一步失败都会删除临时目录，已有的安装保持不变。
+///
+/// # Arguments
+///
+/// * `target` - 最终的安装目录
+/// * `prepare` - 解压到临时目录并检查结果
+/// * `carry_over` - 替换后从原来的安装中保留数据，参数为原来的安装和新的安装
+fn install_dir(
+    target: &Path,
+    prepare: impl FnOnce(&Path) -> Result<(), GdemError>,
+    carry_over: impl FnOnce(&Path, &Path) -> Result<(), GdemError>,
+) -> Result<(), GdemError> {
+    let staging = hidden_sibling(target, STAGING_SUFFIX);
+    let backup = hidden_sibling(target, BACKUP_SUFFIX);
+    // 上次替换中断时，备份是唯一完整的安装
+    if backup.exists() {
+        if target.exists() {
+            fs::remove_dir_all(&backup)?;
+        } else {
+            fs::rename(&backup, target)?;
+        }
+    }
+    if staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+
+    let result = prepare(&staging).and_then(|_| replace_dir(&staging, target, &backup, carry_over));
+    if result.is_err() && staging.exists() {
+        fs::remove_dir_all(&staging)?;
+    }
+    result
+}
 
-fn extract_template(file_path: &Path, data: &Path, file: &str) -> Result<(), Box<dyn Error>> {
-    // file_path  xxx.tpz
-    let target_path = data.join("Godot").join("export_templates");
+/// 用临时目录替换目标目录，失败时恢复原来的安装
+fn replace_dir(
+    staging: &Path,
+    target: &Path,
+    backup: &Path,
+    carry_over: impl FnOnce(&Path, &Path) -> Result<(), GdemError>,
+) -> Result<(), GdemError> {
+    if !target.exists() {
+        fs::rename(staging, target)?;
+        return Ok(());
+    }
+    fs::rename(target, backup)?;
+    let result = fs::rename(staging, target)
+        .map_err(GdemError::from)
+        .and_then(|_| carry_over(backup, target));
+    if let Err(e) = result {
+        if target.exists() {
+            fs::rename(target, staging)?;
+        }
+        fs::rename(backup, target)?;
+        return Err(e);
+    }
+    fs::remove_dir_all(backup)?;
+    Ok(())
+}
 
-    // Godot_v3.6.1-stable_mono_export_templates.tpz -> 3.6.1.stable.mono
-    // Godot_v3.6.1-stable_export_templates.tpz -> 3.6.1.stable
-    let template_dir = file
-        .replace("_export_templates.tpz", "")
-        .replace("Godot_v", "")
-        .replace("_", ".")
-        .replace("-", ".");
-    let target_folder = target_path.join(&template_dir);
+/// 检查解压后的引擎，可执行文件必须存在，mono 版本还需要 GodotSharp 目录
+///
+/// # Returns
+///
+/// * `Result<PathBuf, GdemError>` - 编辑器可执行文件的路径
+fn check_engine_dir(engine_dir: &Path, engine: &str) -> Result<PathBuf, GdemError> {
+    let exe = find_engine_executable(engine_dir)
+        .filter(|exe| exe.metadata().is_ok_and(|m| m.len() > 0))
+        .ok_or_else(|| {
+            GdemError::NotFound(format!("Editor executable not found in {}", engine))
+        })?;
+    if engine.contains("_mono") {
+        let exe_dir = exe.parent().unwrap_or(engine_dir);
+        // macOS 下位于 *.app/Contents/Resources 中
+        let candidates = [
+            exe_dir.join("GodotSharp"),
+            exe_dir.join("..").join("Resources").join("GodotSharp"),
+        ];
+        if !candidates.iter().any(|dir| dir.is_dir()) {
+            Err(GdemError::NotFound(format!(
+                "GodotSharp not found in {}",
+                engine
+            )))?
+        }
+    }
+    Ok(exe)
+}
 
-    extract_zip(file_path, &target_folder)?;
+/// 重新安装时保留自包含模式和 editor_data
+///
+/// 标记文件先创建，editor_data 最后移动，失败时新的安装中不会留下用户数据。
+fn carry_over_engine_data(old: &Path, new: &Path) -> Result<(), GdemError> {
+    if !is_self_contained(old) {
+        return Ok(());
+    }
+    set_self_contained(new, true)?;
+    let old_data = get_editor_data_dir(old);
+    let new_data = get_editor_data_dir(new);
+    if old_data.exists() && !new_data.exists() {
+        fs::rename(old_data, new_data)?;
+    }
     Ok(())
 }
+
+fn extract_engine(
+    file_path: &Path,
+    engine: &str,
+    cfg: &Config,
+    self_contained: bool,
+) -> Result<ExtractReport, GdemError> {
+    let file_name = format_engine_name(engine);
+    let home_dir = get_levels_dir(&cfg.home, engine)?;
+    let limits = cfg.extract_limits();
+    let target_folder = home_dir.join(&file_name);
+
+    let mut report = ExtractReport::default();
+    let prepare = |staging: &Path| {
+        report = extract_zip(file_path, staging, &limits)?;
+        // 压缩包中没有记录权限时，编辑器解压后不能直接运行
+        let exe = check_engine_dir(staging, engine)?;
+        set_executable(&exe)?;
+        // 自包含模式在可执行文件旁创建 _sc_ 文件，编辑器数据保存在 editor_data 中
+        if self_contained {
+            set_self_contained(staging, true)?;
+        }
+        Ok(())
+    };
+    install_dir(&target_folder, prepare, carry_over_engine_data)?;
+    Ok(report)
+}
+
+/// 解压导出模板到模板目录，如 export_templates/3.6.1.stable.mono
+fn extract_template(
+    file_path: &Path,
+    target_folder: &Path,
+    file: &str,
+    limits: &ExtractLimits,
+) -> Result<ExtractReport, GdemError> {
+    let mut report = ExtractReport::default();
+    let prepare = |staging: &Path| {
+        report = extract_zip(file_path, staging, limits)?;
+        if !staging.join("version.txt").is_file() {
+            Err(GdemError::NotFound(format!("version.txt not found in {}", file)))?
+        }
+        Ok(())
+    };
+    install_dir(target_folder, prepare, |_, _| Ok(()))?;
+    Ok(report)
+}
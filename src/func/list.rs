@@ -1,11 +1,10 @@
-use crate::core::handler::DocumentHandler;
-use crate::core::utils::format_size;
-use crate::func::tool::{get_major_from_tag, load_remote_engines_handler};
-use std::collections::HashMap;
-use std::error::Error;
+use crate::core::catalog::{Asset, Release, find_release, load_catalog, parse_version};
+use crate::core::error::{GdemError, not_found_version};
+use crate::core::tags::is_support_file;
+use crate::func::tool::{extract_tag, get_major_from_tag};
 use std::path::Path;
 
-pub fn list_local_engines(home: &Path) -> Result<Vec<String>, Box<dyn Error>> {
+pub fn list_local_engines(home: &Path) -> Result<Vec<String>, GdemError> {
     // 如果 home 目录不存在或无法读取，返回空的Vec
     let mut engine_list: Vec<String> = Vec::new();
 
@@ -39,14 +38,15 @@ pub fn list_local_engines(home: &Path) -> Result<Vec<String>, Box<dyn Error>> {
                 continue;
             }
 
-            // 获取引擎目录名称
+            // 获取引擎目录名称，以 . 开头的是安装时的临时目录
             let engine_dir_names: Vec<String> = version_path
                 .read_dir()?
                 .filter_map(|entry| {
                     entry.ok().and_then(|e| {
                         let path = e.path();
-                        if path.is_dir() {
-                            Some(e.file_name().to_string_lossy().to_string())
+                        let name = e.file_name().to_string_lossy().to_string();
+                        if path.is_dir() && !name.starts_with('.') {
+                            Some(name)
                         } else {
                             None
                         }
@@ -62,153 +62,155 @@ pub fn list_local_engines(home: &Path) -> Result<Vec<String>, Box<dyn Error>> {
     Ok(engine_list)
 }
 
-pub fn list_remote_engines(data: &Path) -> Result<Vec<String>, Box<dyn Error>> {
-    let mut handler = load_remote_engines_handler(data, &["tag_name"])?;
+/// 远程版本的筛选条件
+#[derive(Debug, Default, Clone)]
+pub struct RemoteFilter {
+    pub major: Option<u64>,
+    pub minor: Option<u64>,
+    /// 只保留不低于该版本的发布
+    pub since: Option<(u64, u64, u64)>,
+}
+
+impl RemoteFilter {
+    pub fn is_empty(&self) -> bool {
+        self.major.is_none() && self.minor.is_none() && self.since.is_none()
+    }
 
-    // 在document 中添加major字段
-    for item in handler.document.iter_mut() {
-        let tag_name = item["tag_name"].as_str().unwrap();
-        let major = get_major_from_tag(tag_name);
-        item["major"] = major.into();
+    /// 由 `3`、`4.3` 或 `3.x` 形式的版本号生成筛选条件
+    pub fn from_version(version: &str) -> Option<Self> {
+        let mut parts = version.split('.');
+        let major = parts.next()?.parse::<u64>().ok()?;
+        let minor = parts.next().and_then(|p| p.parse::<u64>().ok());
+        Some(Self {
+            major: Some(major),
+            minor,
+            since: None,
+        })
     }
-    // group by major
-    let mut major_handler = handler
-        .group_by("major", Some(|val_list| val_list))
-        .unwrap();
-    let name_map = HashMap::from([("tag_name".to_string(), "versions".to_string())]);
-    major_handler.rename(&name_map)?;
-
-    // 只获取最新版本的数据
-    // major_handler.document[0]["versions"].as_array().unwrap().to_vec()
-    // 获取max 版本
-    let max_major = major_handler.document.iter().max_by_key(|v| {
-        let major = v["major"].as_str().unwrap();
-        let major = major.chars().nth(0).unwrap();
-        let major = major.to_digit(10).unwrap();
-        major
-    });
-
-    let max_major = max_major.unwrap();
-    let latest_versions = max_major["versions"]
-        .as_array()
-        .unwrap()
-        .to_vec();
-
-    let latest_versions: Vec<String> = latest_versions
+
+    pub fn matches(&self, release: &Release) -> bool {
+        let Some(version) = release.get_version() else {
+            return false;
+        };
+        self.major.is_none_or(|major| version.0 == major)
+            && self.minor.is_none_or(|minor| version.1 == minor)
+            && self.since.is_none_or(|since| version >= since)
+    }
+}
+
+/// 按筛选条件列出远程版本，保持 releases.json 中的顺序
+pub fn list_remote_versions(
+    data: &Path,
+    filter: &RemoteFilter,
+) -> Result<Vec<String>, GdemError> {
+    let releases = load_catalog(data)?;
+    let versions = releases
         .iter()
-        .map(|v| v.as_str().unwrap().to_string())
+        .filter(|r| filter.matches(r))
+        .map(|r| r.tag_name.clone())
         .collect();
-    Ok(latest_versions)
+    Ok(versions)
 }
 
+/// 列出最新主版本的所有远程版本
+pub fn list_remote_engines(data: &Path) -> Result<Vec<String>, GdemError> {
+    let releases = load_catalog(data)?;
+    let max_major = releases
+        .iter()
+        .filter_map(|r| r.get_version().map(|v| v.0))
+        .max()
+        .ok_or(GdemError::NotFound("No remote engines found".to_string()))?;
+    let filter = RemoteFilter {
+        major: Some(max_major),
+        ..Default::default()
+    };
+    list_remote_versions(data, &filter)
+}
+
+/// 列出指定主版本（如 `3`）或次版本（如 `4.3`）的远程版本
 pub fn list_remote_engines_major(
     data: &Path,
     version: &str,
-) -> Result<Vec<String>, Box<dyn Error>> {
-    let mut handler = load_remote_engines_handler(data, &["tag_name"])?;
-
-    // 在document 中添加major字段
-    for item in handler.document.iter_mut() {
-        let tag_name = item["tag_name"].as_str().unwrap();
-        let major = get_major_from_tag(tag_name);
-        item["major"] = major.into();
-    }
-    // group by major
-    let mut major_handler = handler
-        .group_by("major", Some(|val_list| val_list))
-        .unwrap();
-    let name_map = HashMap::from([("tag_name".to_string(), "versions".to_string())]);
-    major_handler.rename(&name_map)?;
-    let major_v = &version.chars().nth(0).unwrap();
-    let major_v = format!("{}.x", major_v);
-    // 找到major_v 对应的元素
-    let major_val = major_handler.document.iter().find(|v| {
-        let major = v["major"].as_str().unwrap();
-        major == major_v
-    });
-    if major_val.is_none() {
-        return Ok(Vec::new());
+) -> Result<Vec<String>, GdemError> {
+    let filter =
+        RemoteFilter::from_version(version)
+        .ok_or_else(|| GdemError::InvalidInput(format!("Invalid version: {}", version)))?;
+    list_remote_versions(data, &filter)
+}
+
+/// 按主版本和次版本分组的版本，如 `4.x` -> `4.4` -> `4.4.1-stable`
+#[derive(Debug, Clone)]
+pub struct VersionGroup {
+    pub name: String,
+    pub minors: Vec<(String, Vec<String>)>,
+}
+
+/// 将版本按主版本和次版本分组，保持原有顺序
+pub fn group_versions(versions: &[String]) -> Vec<VersionGroup> {
+    let mut groups: Vec<VersionGroup> = Vec::new();
+    for version in versions {
+        let major = get_major_from_tag(version);
+        let minor = match parse_version(version) {
+            Some((major, minor, _)) => format!("{}.{}", major, minor),
+            None => major.clone(),
+        };
+        let index = match groups.iter().position(|g| g.name == major) {
+            Some(index) => index,
+            None => {
+                groups.push(VersionGroup {
+                    name: major,
+                    minors: Vec::new(),
+                });
+                groups.len() - 1
+            }
+        };
+        let minors = &mut groups[index].minors;
+        match minors.iter_mut().find(|(name, _)| *name == minor) {
+            Some((_, list)) => list.push(version.clone()),
+            None => minors.push((minor, vec![version.clone()])),
+        }
     }
-    let major_val = major_val.unwrap();
-    let versions = major_val["versions"].as_array().unwrap().clone();
-    // 遍历versions 中的每个元素
-    let versions: Vec<String> = versions
+    groups
+}
+
+/// 获取本地已安装引擎的发布标签，如 `4.4.1-stable`
+pub fn get_installed_tags(home: &Path) -> Result<Vec<String>, GdemError> {
+    let mut tags: Vec<String> = list_local_engines(home)?
         .iter()
-        .map(|v| v.as_str().unwrap().to_string())
+        .filter_map(|engine| extract_tag(engine))
         .collect();
-
-    Ok(versions)
+    tags.sort();
+    tags.dedup();
+    Ok(tags)
 }
 
 /// 列出远程引擎的资产信息
 ///
-/// 该函数从远程数据源加载指定版本的引擎资产信息，并对数据进行处理和格式化。
-/// 处理过程包括：筛选指定版本的资产、提取特定字段、格式化文件大小和更新时间、过滤掉文本文件。
+/// 从发布版本目录中查找指定版本，返回可安装的文件，过滤掉校验文件等不支持的文件。
 ///
 /// # Arguments
 ///
 /// * `data` - 包含releases.json文件的目录路径
 /// * `version` - 要查询的引擎版本号，例如"4.4-stable" 或 "4.4.1"
+/// * `all_platforms` - 是否包含其他系统和架构的编辑器
 ///
 /// # Returns
 ///
-/// * `Result<Vec<Value>, Box<dyn Error>>` - 成功时返回处理后的资产信息列表，失败时返回错误信息
-///
-/// # Process
-///
-/// 1. 使用load_remote_engines_handler加载包含tag_name和assets字段的远程数据
-/// 2. 筛选出tag_name以指定version开头的记录
-/// 3. 提取第一条记录的assets数组
-/// 4. 从assets中提取name、size、updated_at三个字段
-/// 5. 格式化size字段为人类可读的KB/MB/GB格式
-/// 6. 格式化updated_at字段为YYYY-MM-DD格式
-/// 7. 过滤掉以.txt结尾的文件项
+/// * `Result<Vec<Asset>, GdemError>` - 成功时返回资产列表，失败时返回错误信息
 pub fn list_remote_engine_assets(
     data: &Path,
     version: &str,
-) -> Result<Vec<String>, Box<dyn Error>> {
-    let handler = load_remote_engines_handler(data, &["tag_name", "assets"])?;
-    //handler flitter tag_name 中 name 包含 version 的
-    let handler = handler.flitter(|v| {
-        let name = v["tag_name"].as_str().unwrap();
-        // 检查major
-        if name.starts_with(version) {
-            return true;
-        }
-        false
-    })?;
-    // 从handler 中提取assets
-    let latest_assets = handler.document.first().unwrap();
-    let latest_assets = latest_assets["assets"].as_array().unwrap().clone();
-
-    let latest_assets = DocumentHandler::new(latest_assets);
-    let mut assets = latest_assets.get_specific_fields(&["name", "size", "updated_at"])?;
-
-    // 对size进行处理，计算为字符串 KB、MB 或 GB
-    for item in assets.document.iter_mut() {
-        let size = item["size"].as_u64().unwrap() as f64;
-        let size = format_size(size);
-        item["size"] = size.into();
-    }
-    // updated_at 格式化为 2023-01-01 的格式
-    for item in assets.document.iter_mut() {
-        let updated_at = item["updated_at"].as_str().unwrap();
-        let updated_at = updated_at.split("T").collect::<Vec<&str>>()[0];
-        item["updated_at"] = updated_at.into();
-    }
-    // 过滤掉 name 字段 以txt结尾
-    let assets = assets.flitter(|v| {
-        let name = v["name"].as_str().unwrap();
-        if name.ends_with(".txt") {
-            return false;
-        }
-        true
-    })?;
-
-    let assets: Vec<String> = assets
-        .document
+    all_platforms: bool,
+) -> Result<Vec<Asset>, GdemError> {
+    let releases = load_catalog(data)?;
+    let release =
+        find_release(&releases, version).ok_or_else(|| not_found_version(version))?;
+    let assets: Vec<Asset> = release
+        .assets
         .iter()
-        .map(|v| v["name"].as_str().unwrap().to_string())
+        .filter(|a| is_support_file(&a.name) && !a.name.ends_with(".txt"))
+        .filter(|a| all_platforms || a.is_local_platform())
+        .cloned()
         .collect();
     Ok(assets)
 }
@@ -5,3 +5,11 @@ pub mod install;
 pub mod tool;
 pub mod switch;
 pub mod remove;
+pub mod cache;
+pub mod templates;
+pub mod data;
+pub mod search;
+pub mod notes;
+pub mod checksum;
+pub mod preflight;
+pub mod batch;
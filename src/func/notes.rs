@@ -0,0 +1,40 @@
+use crate::core::catalog::{Release, find_release, load_catalog, release_key};
+use crate::core::error::{GdemError, not_found_version};
+use std::path::Path;
+
+/// 获取发布说明
+///
+/// # Arguments
+///
+/// * `data` - 包含releases.json文件的目录路径
+/// * `range` - 单个版本如 `4.4`，或版本范围如 `4.3..4.4`
+///
+/// # Returns
+///
+/// * `Result<Vec<Release>, GdemError>` - 版本范围时返回 from 之后（不含）到 to（含）之间的所有发布，从新到旧排序
+pub fn get_release_notes(data: &Path, range: &str) -> Result<Vec<Release>, GdemError> {
+    let releases = load_catalog(data)?;
+    let find = |version: &str| {
+        find_release(&releases, version)
+            .cloned()
+            .ok_or_else(|| not_found_version(version))
+    };
+
+    let Some((from, to)) = range.split_once("..") else {
+        return Ok(vec![find(range)?]);
+    };
+    let from = release_key(&find(from)?);
+    let to = release_key(&find(to)?);
+    let (from, to) = if from <= to { (from, to) } else { (to, from) };
+
+    let mut notes: Vec<Release> = releases
+        .iter()
+        .filter(|r| {
+            let key = release_key(r);
+            key > from && key <= to
+        })
+        .cloned()
+        .collect();
+    notes.sort_by_key(|r| std::cmp::Reverse(release_key(r)));
+    Ok(notes)
+}
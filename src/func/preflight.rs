@@ -0,0 +1,169 @@
+use crate::core::error::GdemError;
+use crate::core::utils::{symlink, zip_extracted_size};
+use crate::func::config::Config;
+use crate::func::templates::get_template_install_roots;
+use crate::func::tool::{
+    get_levels_path, get_template_dir, get_tpz_template_dir, load_remote_engine_assets,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 压缩包还没有下载时，按下载大小的倍数估算解压后的大小
+const EXTRACT_RATIO: u64 = 3;
+
+/// 检查 GDEM_ROOT 时创建的临时目录和链接，检查后删除
+const PROBE_DIR: &str = ".preflight";
+const PROBE_LINK: &str = ".preflight.link";
+
+/// 安装时需要写入的目录和需要的空间
+#[derive(Debug, Clone)]
+pub struct SpaceNeed {
+    pub path: PathBuf,
+    pub required: u64,
+}
+
+/// 估算安装一个文件需要的空间
+///
+/// 下载需要 releases.json 中记录的文件大小，已缓存时不需要。
+/// 解压需要压缩包中记录的大小，还没有下载时按下载大小的 3 倍估算，最多为解压限制。
+/// 导出模板会解压到每个安装目录中。
+///
+/// # Arguments
+///
+/// * `engine` - 完整的文件名，如 Godot_v4.4.1-stable_win64.exe.zip
+/// * `cfg` - 配置
+pub fn estimate_install(engine: &str, cfg: &Config) -> Result<Vec<SpaceNeed>, GdemError> {
+    let assets = load_remote_engine_assets(engine, &cfg.data)?;
+    let size = assets
+        .iter()
+        .find(|a| a.name == engine)
+        .map_or(0, |a| a.size);
+    let cache_dir = get_levels_path(&cfg.cache, engine)?;
+    let file_path = cache_dir.join(engine);
+
+    let (download, recorded) = if file_path.exists() {
+        (0, zip_extracted_size(&file_path).ok().flatten())
+    } else {
+        (size, None)
+    };
+    let extracted = recorded
+        .unwrap_or(size.saturating_mul(EXTRACT_RATIO))
+        .min(cfg.extract_limits().max_size);
+
+    let mut needs = vec![SpaceNeed {
+        path: cache_dir,
+        required: download,
+    }];
+    if engine.ends_with(".tpz") {
+        // 已下载时与安装时相同，目录名取自 version.txt
+        let template_dir = if file_path.exists() {
+            get_tpz_template_dir(&file_path, engine)?
+        } else {
+            get_template_dir(engine)
+        };
+        for root in get_template_install_roots(&template_dir, cfg)? {
+            needs.push(SpaceNeed {
+                path: root,
+                required: extracted,
+            });
+        }
+    } else if engine.ends_with(".zip") {
+        needs.push(SpaceNeed {
+            path: get_levels_path(&cfg.home, engine)?,
+            required: extracted,
+        });
+    }
+    Ok(needs)
+}
+
+/// 获取最近的已存在的上级目录，安装目录可能还没有创建
+fn existing_ancestor(path: &Path) -> &Path {
+    path.ancestors().find(|p| p.exists()).unwrap_or(path)
+}
+
+/// 文件所在文件系统的标识，用于合并同一磁盘上需要的空间
+#[cfg(unix)]
+fn filesystem_id(path: &Path) -> Option<String> {
+    use std::os::unix::fs::MetadataExt;
+    fs::metadata(path).ok().map(|m| m.dev().to_string())
+}
+
+/// 文件所在文件系统的标识，Windows 下为盘符
+#[cfg(not(unix))]
+fn filesystem_id(path: &Path) -> Option<String> {
+    let path = fs::canonicalize(path).ok()?;
+    let prefix = path.components().next()?;
+    Some(prefix.as_os_str().to_string_lossy().to_string())
+}
+
+/// 检查每个文件系统上的可用空间是否足够
+///
+/// cache 和 home 位于同一磁盘时，需要的空间会合并计算。
+pub fn check_space(needs: &[SpaceNeed]) -> Result<(), GdemError> {
+    // (文件系统标识, 显示的路径, 需要的空间)
+    let mut groups: Vec<(Option<String>, &Path, u64)> = Vec::new();
+    for need in needs.iter().filter(|n| n.required > 0) {
+        let path = existing_ancestor(&need.path);
+        let id = filesystem_id(path);
+        match groups.iter_mut().find(|g| id.is_some() && g.0 == id) {
+            Some(group) => group.2 = group.2.saturating_add(need.required),
+            None => groups.push((id, path, need.required)),
+        }
+    }
+    for (_, path, required) in groups {
+        let available = fs4::available_space(path)?;
+        if required > available {
+            Err(GdemError::InsufficientSpace {
+                path: path.to_path_buf(),
+                required,
+                available,
+            })?
+        }
+    }
+    Ok(())
+}
+
+/// 检查 GDEM_ROOT 是否可写，以及是否可以创建符号链接
+///
+/// 切换引擎和转移 Godot 数据目录都需要创建符号链接，Windows 下需要开启开发者模式或以管理员身份运行。
+pub fn check_root(cfg: &Config) -> Result<(), GdemError> {
+    let probe = cfg.root.join(PROBE_DIR);
+    let link = cfg.root.join(PROBE_LINK);
+    let result = probe_root(&cfg.root, &probe, &link);
+    if link.symlink_metadata().is_ok() {
+        // Windows 下目录链接需要用 remove_dir 删除
+        fs::remove_file(&link).or_else(|_| fs::remove_dir(&link))?;
+    }
+    if probe.exists() {
+        fs::remove_dir_all(&probe)?;
+    }
+    result
+}
+
+fn probe_root(root: &Path, probe: &Path, link: &Path) -> Result<(), GdemError> {
+    fs::create_dir_all(probe)
+        .and_then(|_| fs::write(probe.join("probe"), b"gdem"))
+        .map_err(|e| GdemError::Permission(format!("cannot write to {}: {}", root.display(), e)))?;
+    symlink(probe, link).map_err(|e| {
+        GdemError::Permission(format!(
+            "cannot create symlinks in {}: {}, on Windows enable Developer Mode or run as administrator",
+            root.display(),
+            e
+        ))
+    })
+}
+
+/// 安装前的检查，在下载前发现磁盘空间不足或没有权限的问题
+///
+/// # Arguments
+///
+/// * `engines` - 要安装的完整文件名
+/// * `cfg` - 配置
+pub fn preflight(engines: &[String], cfg: &Config) -> Result<(), GdemError> {
+    check_root(cfg)?;
+    let mut needs = Vec::new();
+    for engine in engines {
+        needs.extend(estimate_install(engine, cfg)?);
+    }
+    check_space(&needs)
+}
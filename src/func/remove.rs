@@ -1,18 +1,59 @@
-use std::error::Error;
-
+use crate::core::config::ConfigTrait;
+use crate::core::error::GdemError;
 use crate::func::{
     config::Config,
-    tool::{format_engine_name, get_levels_dir},
+    tool::{format_engine_name, get_editor_data_dir, get_levels_dir, is_self_contained},
 };
-use std::fs::remove_dir_all;
+use std::fs::{self, remove_dir_all};
+use std::path::PathBuf;
 
-pub fn remove_engine(engine: &str, cfg: &mut Config) -> Result<(), Box<dyn Error>> {
+/// 删除已安装的引擎
+///
+/// 自包含模式的引擎的 `editor_data`（编辑器设置、项目列表和导出模板）默认会移动到
+/// `data/editor_data/<engine>`，已存在时目录名加上时间戳；指定 `purge_data` 时一起删除。
+/// 如果删除的是当前引擎，会同时清除 `default` 链接。
+///
+/// # Returns
+///
+/// * `Result<Option<PathBuf>, GdemError>` - 保留的 editor_data 所在的目录
+pub fn remove_engine(
+    engine: &str,
+    cfg: &mut Config,
+    purge_data: bool,
+) -> Result<Option<PathBuf>, GdemError> {
     let engine_name = format_engine_name(engine);
-    let home_dir = get_levels_dir(&cfg.home, engine);
-    let engine_path = home_dir.join(engine_name);
+    let home_dir = get_levels_dir(&cfg.home, engine)?;
+    let engine_path = home_dir.join(&engine_name);
     if !engine_path.exists() {
-        Err("Engine not found")?
+        Err(GdemError::NotFound(format!(
+            "Engine {} not found, run `gdem ls` to see the installed engines",
+            engine_name
+        )))?
+    }
+
+    let editor_data = get_editor_data_dir(&engine_path);
+    let mut kept = None;
+    if !purge_data && is_self_contained(&engine_path) && editor_data.exists() {
+        let backup_dir = cfg.data.join("editor_data");
+        fs::create_dir_all(&backup_dir)?;
+        let mut backup_path = backup_dir.join(&engine_name);
+        // 不覆盖之前保留的数据
+        if backup_path.exists() {
+            let timestamp = chrono::Local::now().format("%Y%m%d-%H%M%S");
+            backup_path = backup_dir.join(format!("{}-{}", engine_name, timestamp));
+        }
+        fs::rename(&editor_data, &backup_path)?;
+        kept = Some(backup_path);
     }
     remove_dir_all(engine_path)?;
-    Ok(())
+
+    if cfg.version == engine_name {
+        let link_path = cfg.root.join("default");
+        if link_path.is_symlink() {
+            fs::remove_file(&link_path).or_else(|_| fs::remove_dir(&link_path))?;
+        }
+        cfg.switch_version("");
+        cfg.save()?;
+    }
+    Ok(kept)
 }
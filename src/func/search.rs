@@ -0,0 +1,132 @@
+use crate::core::catalog::{Asset, AssetKind, Release, load_catalog, parse_version};
+use crate::core::error::GdemError;
+use crate::core::tags::{Architecture, OS, Tag};
+use std::path::Path;
+
+/// 搜索条件
+#[derive(Debug, Default, Clone)]
+pub struct SearchOptions {
+    /// 只搜索 C# (mono) 版本
+    pub mono: bool,
+    /// 只搜索导出模板
+    pub templates: bool,
+    /// 编辑器的系统，默认为本机系统
+    pub os: Option<OS>,
+    /// 编辑器的架构，默认为本机架构
+    pub arch: Option<Architecture>,
+    /// 发布通道，如 `stable`、`rc`，按前缀匹配
+    pub channel: Option<String>,
+    /// 只保留该日期之后发布的版本，格式为 `2024-01-01`
+    pub after: Option<String>,
+}
+
+impl SearchOptions {
+    fn matches(&self, release: &Release, asset: &Asset) -> bool {
+        let kind = asset.kind();
+        let kind_matched = if self.templates {
+            kind == AssetKind::Templates
+        } else {
+            matches!(kind, AssetKind::Editor | AssetKind::Templates)
+        };
+        if !kind_matched || (self.mono && !asset.is_mono()) {
+            return false;
+        }
+        if kind == AssetKind::Editor {
+            let os = self.os.unwrap_or(OS::get_local_os());
+            let arch = self.arch.unwrap_or(Architecture::get_local_arch());
+            if !os.tag_in(&asset.name) || !arch.tag_in(&asset.name) {
+                return false;
+            }
+        }
+        let channel_matched = self
+            .channel
+            .as_ref()
+            .is_none_or(|c| release.get_channel().starts_with(c.to_lowercase().as_str()));
+        let date_matched = self
+            .after
+            .as_ref()
+            .is_none_or(|after| release.get_date() > after.as_str());
+        channel_matched && date_matched
+    }
+}
+
+/// 搜索结果
+#[derive(Debug, Clone)]
+pub struct SearchHit {
+    pub version: String,
+    pub asset: Asset,
+    pub score: u64,
+}
+
+/// 计算文件与关键字的匹配分数，不匹配时返回 None
+///
+/// 每个关键字都要出现在版本号或文件名中，完全匹配版本号的得分最高，其次是版本号前缀，
+/// 同等条件下稳定版和编辑器优先。
+fn score(release: &Release, asset: &Asset, query: &str) -> Option<u64> {
+    let tag = release.tag_name.to_lowercase();
+    let name = asset.name.to_lowercase();
+    let query = query.trim().to_lowercase();
+    let mut score = 0;
+    for token in query.split_whitespace() {
+        if tag == token {
+            score += 100;
+        } else if tag.split('-').next() == Some(token) {
+            score += 80;
+        } else if tag.starts_with(token) {
+            score += 50;
+        } else if name.contains(token) {
+            score += 10;
+        } else {
+            return None;
+        }
+    }
+    if release.get_channel() == "stable" {
+        score += 5;
+    }
+    if asset.kind() == AssetKind::Editor {
+        score += 2;
+    }
+    Some(score)
+}
+
+/// 在发布版本目录中搜索文件
+///
+/// # Arguments
+///
+/// * `data` - 包含releases.json文件的目录路径
+/// * `query` - 关键字，多个关键字以空格分隔，如 `4.3 mono`
+/// * `options` - 搜索条件
+///
+/// # Returns
+///
+/// * `Result<Vec<SearchHit>, GdemError>` - 按分数和版本从高到低排序的结果
+pub fn search_catalog(
+    data: &Path,
+    query: &str,
+    options: &SearchOptions,
+) -> Result<Vec<SearchHit>, GdemError> {
+    let releases = load_catalog(data)?;
+    let mut hits: Vec<SearchHit> = Vec::new();
+    for release in &releases {
+        for asset in &release.assets {
+            if !options.matches(release, asset) {
+                continue;
+            }
+            if let Some(score) = score(release, asset, query) {
+                hits.push(SearchHit {
+                    version: release.tag_name.clone(),
+                    asset: asset.clone(),
+                    score,
+                });
+            }
+        }
+    }
+    let version_of = |hit: &SearchHit| parse_version(&hit.version);
+    hits.sort_by(|a, b| {
+        b.score
+            .cmp(&a.score)
+            .then(version_of(b).cmp(&version_of(a)))
+            .then(a.asset.name.cmp(&b.asset.name))
+    });
+    Ok(hits)
+}
@@ -1,22 +1,25 @@
 use super::config::Config;
 use crate::core::config::ConfigTrait;
+use crate::core::error::GdemError;
 use crate::core::utils::symlink;
-use crate::func::tool::{format_engine_name, get_levels_dir};
-use std::error::Error;
+use crate::func::tool::{format_engine_name, get_levels_path};
 
-pub fn switch_engine(engine: &str, cfg: &mut Config) -> Result<String, Box<dyn Error>> {
+pub fn switch_engine(engine: &str, cfg: &mut Config) -> Result<String, GdemError> {
     let link_path = cfg.root.join("default");
-    let home_dir = get_levels_dir(&cfg.home, engine);
+    let home_dir = get_levels_path(&cfg.home, engine)?;
     // filename 去除zip和exe
     let engine = format_engine_name(engine);
     let engine_path = home_dir.join(&engine);
-
-    if let Err(e) = symlink(&engine_path, &link_path) {
-        eprintln!("Create link failed: {}", e);
-        return Err(e);
+    // 引擎未安装时不创建指向不存在目录的链接
+    if !engine_path.is_dir() {
+        Err(GdemError::NotFound(format!(
+            "Engine {} not found, run `gdem ls` to see the installed engines",
+            engine
+        )))?
     }
+
+    symlink(&engine_path, &link_path)?;
     cfg.switch_version(&engine);
-    cfg.save();
+    cfg.save()?;
     Ok(engine)
-
 }
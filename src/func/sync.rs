@@ -1,23 +1,29 @@
+use crate::core::error::GdemError;
+use crate::core::style::new_spinner;
 use crate::core::utils::{build_client, save_json};
 use crate::func::config::Config;
-use indicatif::{ProgressBar, ProgressStyle};
 use serde_json::Value;
 
-pub async fn sync_data(cfg: &Config) {
+/// 从 GodotHub 同步引擎版本数据到 data/releases.json
+///
+/// # Returns
+///
+/// * `Result<bool, GdemError>` - 本次是否下载了数据，已存在时返回 false
+pub async fn sync_data(cfg: &Config) -> Result<bool, GdemError> {
     const URL: &str = "https://godothub.atomgit.net/web/api/releases.json";
-    let pb = ProgressBar::new_spinner();
-    pb.set_style(ProgressStyle::default_spinner());
-    pb.enable_steady_tick(std::time::Duration::from_millis(80));
+    let pb = new_spinner();
     pb.set_message("Syncing data...");
 
     let file_path = cfg.data.join("releases.json");
     if file_path.exists() {
-        return;
+        pb.finish_and_clear();
+        return Ok(false);
     }
-    let client = build_client(Some(cfg.proxy.as_str())).unwrap();
-    let resp = client.get(URL).send().await.unwrap();
+    let client = build_client(Some(cfg.proxy.as_str()))?;
+    let resp = client.get(URL).send().await?;
     // json
-    let res = resp.json::<Value>().await.unwrap();
-    save_json(&res, &file_path).unwrap();
+    let res = resp.json::<Value>().await?;
+    save_json(&res, &file_path)?;
     pb.finish_with_message("Sync data done.");
+    Ok(true)
 }
@@ -0,0 +1,263 @@
+use crate::core::error::GdemError;
+use crate::func::config::Config;
+use crate::func::list::list_local_engines;
+use crate::func::tool::{
+    get_editor_data_dir, get_engine_dir, get_engine_template_dir,
+    is_self_contained, load_remote_engine_assets,
+};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// 已安装的一组导出模板
+#[derive(Debug, Clone)]
+pub struct TemplateSet {
+    /// 模板目录名，如 4.4.1.stable.mono
+    pub name: String,
+    pub path: PathBuf,
+    /// 模板目录中 version.txt 的内容
+    pub version: Option<String>,
+    /// 使用该模板的已安装引擎
+    pub engines: Vec<String>,
+    /// 模板所属的自包含引擎，共享目录中的模板为 None
+    pub owner: Option<String>,
+}
+
+impl TemplateSet {
+    /// 没有任何已安装的引擎使用该模板
+    pub fn is_orphaned(&self) -> bool {
+        self.engines.is_empty()
+    }
+}
+
+/// 各主版本导出模板中必须包含的文件
+const REQUIRED_FILES_4: &[&str] = &[
+    "version.txt",
+    "windows_debug_x86_64.exe",
+    "windows_release_x86_64.exe",
+    "linux_debug.x86_64",
+    "linux_release.x86_64",
+    "macos.zip",
+    "android_debug.apk",
+    "android_release.apk",
+];
+const REQUIRED_FILES_3: &[&str] = &[
+    "version.txt",
+    "windows_64_debug.exe",
+    "windows_64_release.exe",
+    "linux_x11_64_debug",
+    "linux_x11_64_release",
+    "osx.zip",
+    "android_debug.apk",
+    "android_release.apk",
+];
+
+/// 导出模板的校验结果
+#[derive(Debug, Clone)]
+pub struct TemplateCheck {
+    pub set: TemplateSet,
+    /// 目录名与 version.txt 是否一致
+    pub name_matched: bool,
+    /// 缺失的文件
+    pub missing: Vec<String>,
+}
+
+impl TemplateCheck {
+    pub fn is_ok(&self) -> bool {
+        self.name_matched && self.missing.is_empty() && !self.set.is_orphaned()
+    }
+}
+
+/// 获取共享的导出模板目录
+pub fn get_templates_root(data: &Path) -> PathBuf {
+    data.join("Godot").join("export_templates")
+}
+
+/// 导出模板目录
+#[derive(Debug, Clone)]
+pub struct TemplateRoot {
+    pub path: PathBuf,
+    /// 目录所属的自包含引擎，共享目录为 None
+    pub owner: Option<String>,
+}
+
+/// 获取所有导出模板目录，以及目录所属的自包含引擎
+///
+/// 共享目录为 `data/Godot/export_templates`，自包含引擎的模板位于 `editor_data/export_templates`。
+pub fn get_template_roots(cfg: &Config) -> Result<Vec<TemplateRoot>, GdemError> {
+    let mut roots = vec![TemplateRoot {
+        path: get_templates_root(&cfg.data),
+        owner: None,
+    }];
+    for engine in list_local_engines(&cfg.home)? {
+        let engine_dir = get_engine_dir(&cfg.home, &engine)?;
+        if is_self_contained(&engine_dir) {
+            roots.push(TemplateRoot {
+                path: get_editor_data_dir(&engine_dir).join("export_templates"),
+                owner: Some(engine),
+            });
+        }
+    }
+    Ok(roots)
+}
+
+/// 获取导出模板应当安装到的目录
+///
+/// 与模板版本匹配的自包含引擎使用各自的 `editor_data/export_templates`，
+/// 有匹配的普通引擎或没有任何匹配的引擎时，安装到共享目录。
+///
+/// # Arguments
+///
+/// * `template_dir` - 模板的目录名，如 4.4.1.stable.mono，见 `get_tpz_template_dir`
+/// * `cfg` - 配置
+pub fn get_template_install_roots(
+    template_dir: &str,
+    cfg: &Config,
+) -> Result<Vec<PathBuf>, GdemError> {
+    let mut roots: Vec<PathBuf> = Vec::new();
+    let mut matched = false;
+    let mut shared = false;
+    for engine in list_local_engines(&cfg.home)? {
+        if get_engine_template_dir(&engine).as_deref() != Some(template_dir) {
+            continue;
+        }
+        matched = true;
+        let engine_dir = get_engine_dir(&cfg.home, &engine)?;
+        if is_self_contained(&engine_dir) {
+            roots.push(get_editor_data_dir(&engine_dir).join("export_templates"));
+        } else {
+            shared = true;
+        }
+    }
+    if shared || !matched {
+        roots.insert(0, get_templates_root(&cfg.data));
+    }
+    Ok(roots)
+}
+
+/// 列出已安装的导出模板，并找出与之匹配的已安装引擎
+///
+/// 共享目录中的模板只匹配非自包含的引擎，自包含引擎只使用自己目录中的模板。
+pub fn list_templates(cfg: &Config) -> Result<Vec<TemplateSet>, GdemError> {
+    let mut sets: Vec<TemplateSet> = Vec::new();
+    let engines: Vec<String> = list_local_engines(&cfg.home)?
+        .into_iter()
+        .filter(|e| !get_engine_dir(&cfg.home, e).is_ok_and(|dir| is_self_contained(&dir)))
+        .collect();
+
+    for TemplateRoot { path: root, owner } in get_template_roots(cfg)? {
+        let entries = match root.read_dir() {
+            Ok(entries) => entries,
+            Err(_) => continue,
+        };
+        for entry in entries {
+            let path = entry?.path();
+            if !path.is_dir() {
+                continue;
+            }
+            let name = path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            // 以 . 开头的是安装时的临时目录
+            if name.starts_with('.') {
+                continue;
+            }
+            let version = fs::read_to_string(path.join("version.txt"))
+                .ok()
+                .map(|v| v.trim().to_string());
+            let candidates = match &owner {
+                Some(owner) => std::slice::from_ref(owner),
+                None => engines.as_slice(),
+            };
+            let matched: Vec<String> = candidates
+                .iter()
+                .filter(|e| get_engine_template_dir(e).as_deref() == Some(name.as_str()))
+                .cloned()
+                .collect();
+            sets.push(TemplateSet {
+                name,
+                path,
+                version,
+                engines: matched,
+                owner: owner.clone(),
+            });
+        }
+    }
+
+    sets.sort_by(|a, b| a.name.cmp(&b.name));
+    Ok(sets)
+}
+
+/// 根据版本号和是否为 mono 版本，查找导出模板的资产文件名
+///
+/// # Arguments
+///
+/// * `version` - 引擎版本号，例如 "4.4.1" 或 "4.3-stable"
+/// * `mono` - 是否为 C# (mono) 版本的模板
+/// * `data` - 包含 releases.json 文件的目录路径
+///
+/// # Returns
+///
+/// * `Result<String, GdemError>` - 导出模板的文件名，如 Godot_v4.4.1-stable_export_templates.tpz
+pub fn query_template_asset(
+    version: &str,
+    mono: bool,
+    data: &Path,
+) -> Result<String, GdemError> {
+    let assets = load_remote_engine_assets(version, data)?;
+    let asset = assets
+        .iter()
+        .find(|a| a.name.ends_with("_export_templates.tpz") && a.name.contains("_mono") == mono)
+        .map(|a| a.name.clone());
+    asset.ok_or_else(|| {
+        GdemError::NotFound(format!("Export templates for {} not found", version))
+    })
+}
+
+/// 删除已安装的导出模板，包括自包含引擎目录中的同名模板
+pub fn remove_templates(name: &str, cfg: &Config) -> Result<(), GdemError> {
+    let sets: Vec<TemplateSet> = list_templates(cfg)?
+        .into_iter()
+        .filter(|t| t.name == name)
+        .collect();
+    if sets.is_empty() {
+        Err(GdemError::NotFound(format!(
+            "Export templates {} not found, run `gdem templates ls` to see the installed templates",
+            name
+        )))?
+    }
+    for set in sets {
+        fs::remove_dir_all(set.path)?;
+    }
+    Ok(())
+}
+
+/// 校验所有已安装的导出模板
+///
+/// 检查目录名是否与 version.txt 一致、文件是否完整，以及是否有匹配的已安装引擎。
+pub fn verify_templates(cfg: &Config) -> Result<Vec<TemplateCheck>, GdemError> {
+    let sets = list_templates(cfg)?;
+    let checks = sets
+        .into_iter()
+        .map(|set| {
+            let name_matched = set.version.as_deref() == Some(set.name.as_str());
+            let required = match set.name.split('.').next() {
+                Some("4") => REQUIRED_FILES_4,
+                Some("3") => REQUIRED_FILES_3,
+                _ => &["version.txt"],
+            };
+            let missing = required
+                .iter()
+                .filter(|f| !set.path.join(f).exists())
+                .map(|f| f.to_string())
+                .collect();
+            TemplateCheck {
+                set,
+                name_matched,
+                missing,
+            }
+        })
+        .collect();
+    Ok(checks)
+}
@@ -1,18 +1,42 @@
-use crate::core::handler::DocumentHandler;
+use crate::core::catalog::{Asset, load_catalog};
+use crate::core::error::{GdemError, not_found_version};
 use crate::core::tags::is_support_file;
-use crate::core::tags::{Architecture, OS, Tag};
-use crate::core::utils::format_size;
-use crate::func::list::list_remote_engine_assets;
+use crate::core::utils::{archive_entry_path, read_zip_entry};
 use regex::Regex;
-use std::error::Error;
 use std::fs;
 use std::path::{Path, PathBuf};
 
-pub fn get_asset_name(asset: &str, data: &Path) -> String {
-    let version = extract_version(asset).unwrap();
-    let assets = list_remote_engine_assets(data, &version).unwrap();
-    let asset = assets.iter().find(|a| a.starts_with(asset)).unwrap();
-    asset.to_string()
+/// 根据文件名或文件名前缀查找远程文件的完整文件名
+///
+/// 前缀匹配到多个文件时返回 `AmbiguousAsset` 错误。
+pub fn get_asset_name(asset: &str, data: &Path) -> Result<String, GdemError> {
+    let assets = load_remote_engine_assets(asset, data)?;
+    if let Some(exact) = assets.iter().find(|a| a.name == asset) {
+        return Ok(exact.name.clone());
+    }
+    let candidates: Vec<String> = assets
+        .iter()
+        .filter(|a| a.name.starts_with(asset) && !a.name.ends_with(".txt"))
+        .map(|a| a.name.clone())
+        .collect();
+    match candidates.len() {
+        0 => Err(GdemError::NotFound(format!(
+            "{} not found, run `gdem ls -v <version>` to see the available files",
+            asset
+        ))),
+        1 => Ok(candidates[0].clone()),
+        _ => Err(GdemError::AmbiguousAsset {
+            query: asset.to_string(),
+            candidates,
+        }),
+    }
+}
+
+fn no_version(engine: &str) -> GdemError {
+    GdemError::InvalidInput(format!(
+        "Cannot find the version in {}, e.g. Godot_v4.4.1-stable_win64",
+        engine
+    ))
 }
 
 pub fn format_engine_name(engine: &str) -> String {
@@ -21,14 +45,200 @@ pub fn format_engine_name(engine: &str) -> String {
 }
 
 /// 根据文件，获取层次目录，之和版本相关
-pub fn get_levels_dir(root: &Path, engine: &str) -> PathBuf {
-    let version = extract_version(engine).unwrap();
-    let major = get_major_from_tag(version.as_str());
-    let l_dir = root.join(major).join(version);
+pub fn get_levels_dir(root: &Path, engine: &str) -> Result<PathBuf, GdemError> {
+    let l_dir = get_levels_path(root, engine)?;
     if !l_dir.exists() {
-        fs::create_dir_all(&l_dir).unwrap();
+        fs::create_dir_all(&l_dir)?;
+    }
+    Ok(l_dir)
+}
+
+/// 根据文件，获取层次目录的路径，但不创建目录
+pub fn get_levels_path(root: &Path, engine: &str) -> Result<PathBuf, GdemError> {
+    let version = extract_version(engine).ok_or_else(|| no_version(engine))?;
+    let major = get_major_from_tag(version.as_str());
+    Ok(root.join(major).join(version))
+}
+
+/// 自包含模式的标记文件，放在编辑器可执行文件旁边
+pub const SELF_CONTAINED_FILE: &str = "_sc_";
+
+/// 获取已安装引擎的目录，如 home/4.x/4.4.1/Godot_v4.4.1-stable_win64
+pub fn get_engine_dir(home: &Path, engine: &str) -> Result<PathBuf, GdemError> {
+    Ok(get_levels_path(home, engine)?.join(format_engine_name(engine)))
+}
+
+/// 在引擎目录中查找编辑器可执行文件
+///
+/// Windows 和 Linux 下可执行文件位于引擎目录中，macOS 下位于 `*.app/Contents/MacOS` 中。
+/// 控制台版本（`_console`）会被忽略。
+pub fn find_engine_executable(engine_dir: &Path) -> Option<PathBuf> {
+    const SKIP_EXTS: &[&str] = &["pck", "zip", "txt", "dll", "so", "dylib", "json", "cfg"];
+    let mut entries: Vec<PathBuf> = fs::read_dir(engine_dir)
+        .ok()?
+        .filter_map(|entry| entry.ok().map(|e| e.path()))
+        .collect();
+    entries.sort();
+
+    for path in entries {
+        let name = path.file_name()?.to_string_lossy().to_string();
+        if path.is_dir() && name.ends_with(".app") {
+            let macos_dir = path.join("Contents").join("MacOS");
+            let exe = fs::read_dir(macos_dir)
+                .ok()?
+                .filter_map(|entry| entry.ok().map(|e| e.path()))
+                .find(|p| p.is_file());
+            if exe.is_some() {
+                return exe;
+            }
+        } else if path.is_file() && name.starts_with("Godot") && !name.contains("_console") {
+            let ext = path
+                .extension()
+                .map(|e| e.to_string_lossy().to_lowercase())
+                .unwrap_or_default();
+            if !SKIP_EXTS.contains(&ext.as_str()) {
+                return Some(path);
+            }
+        }
     }
-    l_dir
+    None
+}
+
+/// 判断引擎是否为自包含模式，即可执行文件旁存在 `_sc_` 或 `._sc_` 文件
+pub fn is_self_contained(engine_dir: &Path) -> bool {
+    match find_engine_executable(engine_dir).as_deref().and_then(Path::parent) {
+        Some(exe_dir) => {
+            exe_dir.join(SELF_CONTAINED_FILE).exists()
+                || exe_dir.join(format!(".{}", SELF_CONTAINED_FILE)).exists()
+        }
+        None => false,
+    }
+}
+
+/// 开启或关闭引擎的自包含模式
+///
+/// 开启后编辑器的设置、数据和导出模板都保存在可执行文件旁的 `editor_data` 目录中。
+pub fn set_self_contained(engine_dir: &Path, enabled: bool) -> Result<(), GdemError> {
+    let exe = find_engine_executable(engine_dir)
+        .ok_or_else(|| {
+        GdemError::NotFound(format!(
+            "Editor executable not found in {}",
+            engine_dir.display()
+        ))
+    })?;
+    let exe_dir = exe.parent().unwrap_or(engine_dir);
+    let sc_file_path = exe_dir.join(SELF_CONTAINED_FILE);
+    if enabled {
+        fs::File::create(sc_file_path)?;
+    } else {
+        for sc_file in [sc_file_path, exe_dir.join(format!(".{}", SELF_CONTAINED_FILE))] {
+            if sc_file.exists() {
+                fs::remove_file(sc_file)?;
+            }
+        }
+    }
+    Ok(())
+}
+
+/// 获取自包含模式下引擎的编辑器数据目录
+pub fn get_editor_data_dir(engine_dir: &Path) -> PathBuf {
+    find_engine_executable(engine_dir)
+        .as_deref()
+        .and_then(Path::parent)
+        .unwrap_or(engine_dir)
+        .join("editor_data")
+}
+
+/// 根据导出模板文件名，获取模板安装的目录名
+///
+/// # Examples
+///
+/// ```
+/// use gdem::func::tool::get_template_dir;
+/// let dir = get_template_dir("Godot_v3.6.1-stable_mono_export_templates.tpz");
+/// assert_eq!(dir, "3.6.1.stable.mono");
+/// ```
+pub fn get_template_dir(file: &str) -> String {
+    file.replace("_export_templates.tpz", "")
+        .replace("Godot_v", "")
+        .replace("_", ".")
+        .replace("-", ".")
+}
+
+/// 判断是否为导出模板的目录名，格式为 `<major>.<minor>[.<patch>].<status>[.mono]`
+///
+/// # Examples
+///
+/// ```
+/// use gdem::func::tool::is_template_dir_name;
+/// assert!(is_template_dir_name("4.4.1.stable.mono"));
+/// assert!(is_template_dir_name("4.3.rc1"));
+/// assert!(!is_template_dir_name("../../.."));
+/// assert!(!is_template_dir_name("4.4.1/stable"));
+/// ```
+pub fn is_template_dir_name(name: &str) -> bool {
+    let re = Regex::new(r#"^\d+\.\d+(?:\.\d+)?\.[a-zA-Z]+\d*(?:\.mono)?$"#).unwrap();
+    re.is_match(name)
+}
+
+/// 获取导出模板压缩包安装的目录名
+///
+/// 目录名必须与 `templates/version.txt` 一致，否则 Godot 无法找到模板，
+/// 压缩包中没有 version.txt 时按文件名推断。
+/// version.txt 的内容不是模板版本时返回 `UnsafeArchive` 错误，它会被用作安装路径。
+///
+/// # Arguments
+///
+/// * `file_path` - 导出模板压缩包的路径
+/// * `file` - 完整的文件名，如 Godot_v3.6.1-stable_mono_export_templates.tpz
+pub fn get_tpz_template_dir(file_path: &Path, file: &str) -> Result<String, GdemError> {
+    let version = match read_zip_entry(file_path, "templates/version.txt")? {
+        Some(version) if !version.trim().is_empty() => version.trim().to_string(),
+        _ => return Ok(get_template_dir(file)),
+    };
+    // 与压缩包中的条目路径相同，拒绝绝对路径和 `..`，并且只能是一级目录
+    let path = archive_entry_path(&version)?;
+    if path.components().count() != 1 || !is_template_dir_name(&version) {
+        Err(GdemError::UnsafeArchive(format!(
+            "{}: templates/version.txt is not a template version: {}",
+            file, version
+        )))?
+    }
+    Ok(version)
+}
+
+/// 根据引擎名，获取其对应的导出模板目录名
+///
+/// # Examples
+///
+/// ```
+/// use gdem::func::tool::get_engine_template_dir;
+/// let dir = get_engine_template_dir("Godot_v4.4.1-stable_mono_win64");
+/// assert_eq!(dir, Some("4.4.1.stable.mono".to_string()));
+/// ```
+pub fn get_engine_template_dir(engine: &str) -> Option<String> {
+    let re = Regex::new(r#"Godot_v([^_]+)"#).unwrap();
+    let tag = re.captures(engine)?.get(1)?.as_str().replace("-", ".");
+    if engine.contains("_mono") {
+        Some(format!("{}.mono", tag))
+    } else {
+        Some(tag)
+    }
+}
+
+/// 从文件名中提取发布标签，如 `Godot_v4.4.1-stable_win64.exe.zip` -> `4.4.1-stable`
+///
+/// # Examples
+///
+/// ```
+/// use gdem::func::tool::extract_tag;
+/// assert_eq!(extract_tag("Godot_v4.4-rc1_mono_win64"), Some("4.4-rc1".to_string()));
+/// assert_eq!(extract_tag("4.4.1"), None);
+/// ```
+pub fn extract_tag(engine: &str) -> Option<String> {
+    let re = Regex::new(r#"(\d+\.\d+(?:\.\d+)?-[a-zA-Z]+\d*)"#).unwrap();
+    re.captures(engine)
+        .map(|captures| captures.get(1).unwrap().as_str().to_string())
 }
 
 /// 从文件名中提取版本号
@@ -47,42 +257,6 @@ pub fn extract_version(engine: &str) -> Option<String> {
         .map(|captures| captures.get(1).unwrap().as_str().to_string())
 }
 
-pub fn load_remote_engines_handler(
-    data: &Path,
-    fields: &[&str],
-) -> Result<DocumentHandler, Box<dyn Error>> {
-    let file_path = data.join("releases.json");
-    let mut handler = DocumentHandler::load_data(&file_path)?;
-    handler.apply("assets", |v| {
-        let assets = v.as_array().unwrap();
-        let assets = DocumentHandler::new(assets.clone());
-        // 对assets 字段进行删除，只保留 name size updated_at browser_download_url
-        let fields = ["name", "size", "updated_at", "browser_download_url"];
-        let assets = assets.get_specific_fields(&fields).unwrap();
-        // is_support_extension 过滤assets 中不支持的文件
-        // 只保留本机系统和架构
-        let local_os = OS::get_local_os();
-        let local_arch = Architecture::get_local_arch();
-        let assets = assets
-            .flitter(|v| {
-                let name = v["name"].as_str().unwrap();
-                let is_supported = is_support_file(name);
-                // 如果是zip，判断系统和架构
-                let mut flag = true;
-                if name.ends_with(".zip") {
-                    let is_os = local_os.tag_in(name);
-                    let is_arch = local_arch.tag_in(name);
-                    flag = is_os && is_arch;
-                }
-                flag && is_supported
-            })
-            .unwrap();
-        assets.document.into()
-    })?;
-    let handler = handler.get_specific_fields(fields)?;
-    Ok(handler)
-}
-
 /// 从tag_name 中提取major 版本号
 ///
 /// # Arguments
@@ -107,10 +281,9 @@ pub fn get_major_from_tag(tag_name: &str) -> String {
     format!("{}.x", major)
 }
 
-/// 加载远程引擎资源并处理数据格式
+/// 加载远程引擎资源
 ///
-/// 该函数从指定路径加载远程引擎的发布数据，提取与指定版本匹配的资源，
-/// 并对资源数据进行处理，包括文件大小格式化和更新时间格式化。
+/// 从发布版本目录中提取与文件名中的版本匹配的资源。
 ///
 /// # Arguments
 ///
@@ -119,47 +292,33 @@ pub fn get_major_from_tag(tag_name: &str) -> String {
 ///
 /// # Returns
 ///
-/// * `Result<DocumentHandler, Box<dyn Error>>` - 成功时返回处理后的资源数据，失败时返回错误信息
+/// * `Result<Vec<Asset>, GdemError>` - 成功时返回资源列表，失败时返回错误信息
 ///
 /// # Process
 ///
-/// 1. 从文件名中提取版本号
-/// 2. 加载远程引擎处理句柄
-/// 3. 过滤出与版本号匹配的资源
-/// 4. 提取匹配资源的资产信息
-/// 5. 格式化资产中的文件大小（转换为 KB、MB 或 GB）
-/// 6. 格式化更新时间（转换为 YYYY-MM-DD 格式）
-pub fn load_remote_engine_assets(
-    file_name: &str,
-    data: &Path,
-) -> Result<DocumentHandler, Box<dyn Error>> {
-    let version = extract_version(file_name).unwrap();
-
-    let handler = load_remote_engines_handler(data, &["tag_name", "assets"])?;
-    let handler = handler.flitter(|v| {
-        let name = v["tag_name"].as_str().unwrap();
-        // 检查major
-        if name.starts_with(version.as_str()) {
-            return true;
-        }
-        false
-    })?;
-    // 从handler 中提取assets
-    let latest_assets = handler.document.first().unwrap();
-    let latest_assets = latest_assets["assets"].as_array().unwrap().clone();
-
-    let mut assets = DocumentHandler::new(latest_assets);
-    // 对size进行处理，计算为字符串 KB、MB 或 GB
-    for item in assets.document.iter_mut() {
-        let size = item["size"].as_u64().unwrap() as f64;
-        let size = format_size(size);
-        item["size"] = size.into();
-    }
-    // updated_at 格式化为 2023-01-01 的格式
-    for item in assets.document.iter_mut() {
-        let updated_at = item["updated_at"].as_str().unwrap();
-        let updated_at = updated_at.split("T").collect::<Vec<&str>>()[0];
-        item["updated_at"] = updated_at.into();
-    }
+/// 1. 从文件名中提取版本号和发布标签
+/// 2. 查找与发布标签（没有标签时为版本号）匹配的发布版本
+/// 3. 过滤掉不支持的文件以及其他系统和架构的编辑器
+pub fn load_remote_engine_assets(file_name: &str, data: &Path) -> Result<Vec<Asset>, GdemError> {
+    let version = extract_version(file_name).ok_or_else(|| no_version(file_name))?;
+    // 带有完整标签时精确匹配，避免 4.2-stable 匹配到 4.2.2-stable
+    let tag = extract_tag(file_name);
+
+    let releases = load_catalog(data)?;
+    let release = releases
+        .iter()
+        .find(|r| match &tag {
+            Some(tag) => r.tag_name == *tag,
+            None => r.tag_name.starts_with(version.as_str()),
+        })
+        .ok_or_else(|| not_found_version(&version))?;
+
+    // 过滤不支持的文件，编辑器只保留本机系统和架构
+    let assets = release
+        .assets
+        .iter()
+        .filter(|a| is_support_file(&a.name) && a.is_local_platform())
+        .cloned()
+        .collect();
     Ok(assets)
 }
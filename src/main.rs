@@ -1,8 +1,23 @@
-use clap::{Parser, Subcommand};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use colored::Colorize;
+use gdem::core::catalog::parse_version;
 use gdem::core::config::ConfigTrait;
+use gdem::core::error::GdemError;
+use gdem::core::output::{self, Output, OutputFormat};
 use gdem::core::source::Source;
 use gdem::core::style;
-use gdem::func::{config, install, list, remove, switch, sync};
+use gdem::core::tags::{Architecture, OS, Tag};
+use gdem::core::utils::{format_size, parse_size};
+use gdem::func::tool::{extract_tag, get_engine_dir, set_self_contained};
+use gdem::func::{
+    batch, cache, checksum, config, data, install, list, notes, remove, search, switch, sync,
+    templates,
+};
+use serde_json::json;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+type CommandResult = Result<Output, GdemError>;
 
 #[derive(Parser)]
 #[clap(
@@ -12,6 +27,9 @@ use gdem::func::{config, install, list, remove, switch, sync};
     after_help = "Before using, please first sync the data with `gdem sync`."
 )]
 struct Cli {
+    /// The output format, json and plain are meant for scripts.
+    #[clap(long, value_enum, default_value = "text", global = true)]
+    output: OutputFormat,
     #[clap(subcommand)]
     command: Commands,
 }
@@ -27,6 +45,20 @@ enum Commands {
         /// The proxy to use.
         #[clap(short, long)]
         proxy: Option<String>,
+        /// Install engines in self-contained mode by default, and apply it to the current engine.
+        /// Pass `false` to turn it off.
+        #[clap(
+            long,
+            alias = "sc",
+            value_name = "BOOL",
+            num_args = 0..=1,
+            default_missing_value = "true"
+        )]
+        self_contained: Option<bool>,
+        #[clap(flatten)]
+        download: DownloadArgs,
+        #[clap(flatten)]
+        extract: ExtractArgs,
     },
     /// Sync the data from GodotHub.
     #[clap(name = "sync", alias = "s")]
@@ -40,19 +72,80 @@ enum Commands {
         /// List the engine assets.
         #[clap(short, long)]
         version: Option<String>,
+        /// Include the assets of other systems and architectures.
+        #[clap(long)]
+        all_platforms: bool,
+        /// Only list the remote engines of this major version, e.g. 4.
+        #[clap(long)]
+        major: Option<u64>,
+        /// Only list the remote engines of this minor version, used with --major.
+        #[clap(long, requires = "major")]
+        minor: Option<u64>,
+        /// Only list the remote engines since this version, e.g. 4.2.
+        #[clap(long, value_name = "VERSION")]
+        since: Option<String>,
+        /// List all remote engines grouped by major and minor version.
+        #[clap(short, long)]
+        all: bool,
+    },
+    /// Search the engines and export templates, the names of the editors for this system can be passed to install.
+    #[clap(name = "search", alias = "find")]
+    Search {
+        /// Keywords, e.g. "4.3 mono" or "rc".
+        query: Option<String>,
+        /// Only the C# (mono) flavor.
+        #[clap(long)]
+        mono: bool,
+        /// Only the export templates.
+        #[clap(short, long)]
+        templates: bool,
+        /// The system of the editors, e.g. windows, linux or macos. Defaults to this system.
+        #[clap(long, value_parser = parse_tag::<OS>)]
+        os: Option<OS>,
+        /// The architecture of the editors, e.g. x86_64 or arm64. Defaults to this architecture.
+        #[clap(long, value_parser = parse_tag::<Architecture>)]
+        arch: Option<Architecture>,
+        /// The release channel, e.g. stable, rc or beta.
+        #[clap(short, long)]
+        channel: Option<String>,
+        /// Only releases published after the date, e.g. 2024-01-01.
+        #[clap(long, value_name = "DATE", value_parser = parse_date)]
+        after: Option<String>,
+        /// The maximum number of results.
+        #[clap(short = 'n', long, default_value = "20")]
+        limit: usize,
+    },
+    /// Show the release notes of a version, or of every version in a range.
+    #[clap(name = "notes", alias = "changelog")]
+    Notes {
+        /// The version, e.g. 4.4, or a range, e.g. 4.3..4.4.
+        range: String,
     },
     /// Install the engine.
     #[clap(name = "install", alias = "i")]
     Install {
-        /// The engine version to install.
+        /// The engine versions to install, several engines are downloaded in parallel.
         /// Godot_v4.4.1-stable_mono_win64.zip
-        engine: String,
+        #[clap(required_unless_present = "from")]
+        engines: Vec<String>,
+        /// Also install the engines and export templates listed in a gdem.toml file.
+        #[clap(long, value_name = "FILE")]
+        from: Option<PathBuf>,
         #[clap(short, long)]
         /// Force install.
         force: bool,
         #[clap(short = 'k', long)]
         /// Skip sha512 check.
         skip_check: bool,
+        #[clap(long, conflicts_with = "skip_check")]
+        /// Download once more if the checksum does not match.
+        redownload: bool,
+        #[clap(short = 't', long)]
+        /// Also install the export templates of the same version and flavor.
+        with_templates: bool,
+        #[clap(long, alias = "sc")]
+        /// Install in self-contained mode, keeping settings and templates in editor_data.
+        self_contained: bool,
     },
     /// Switch the engine.
     #[clap(name = "switch", alias = "sw")]
@@ -67,90 +160,1193 @@ enum Commands {
         /// The local engine to remove.
         /// Godot_v4.4.1-stable_mono_win64
         engine: String,
+        /// Delete the editor_data of a self-contained engine instead of keeping it under data/editor_data.
+        #[clap(long)]
+        purge_data: bool,
+    },
+    /// Re-check the cached archives against the checksums of their release.
+    #[clap(name = "verify")]
+    Verify {
+        /// The cached archive or engine to check, all cached archives when omitted.
+        /// Godot_v4.4.1-stable_win64.exe.zip
+        engine: Option<String>,
+    },
+    /// Manage the download cache.
+    #[clap(name = "cache")]
+    Cache {
+        #[clap(subcommand)]
+        command: CacheCommands,
+    },
+    /// Manage the export templates.
+    #[clap(name = "templates", alias = "tpl")]
+    Templates {
+        #[clap(subcommand)]
+        command: TemplatesCommands,
+    },
+    /// Manage the Godot editor data.
+    #[clap(name = "data")]
+    Data {
+        #[clap(subcommand)]
+        command: DataCommands,
+    },
+}
+
+#[derive(Subcommand)]
+enum DataCommands {
+    /// Show where each Godot data directory lives now.
+    #[clap(name = "status", alias = "st")]
+    Status,
+    /// Move the Godot data directories into GDEM_ROOT/data and link them back.
+    #[clap(name = "link")]
+    Link,
+    /// Replace the links with real copies of the data, undoing the relocation.
+    #[clap(name = "unlink")]
+    Unlink,
+    /// Back up the editor settings, projects list and script templates.
+    #[clap(name = "backup")]
+    Backup {
+        /// Extra data to include in the backup.
+        #[clap(short, long, value_enum, value_delimiter = ',')]
+        include: Vec<BackupInclude>,
+    },
+    /// Restore the editor data from a backup archive.
+    #[clap(name = "restore")]
+    Restore {
+        /// The backup archive, e.g. godot-data-20250101-120000.tar.gz
+        archive: PathBuf,
+        /// Restore without asking for confirmation.
+        #[clap(short, long)]
+        yes: bool,
+    },
+}
+
+#[derive(Args)]
+struct DownloadArgs {
+    /// The number of connections used to download a file, 1 disables segmented downloads.
+    #[clap(short, long, value_parser = clap::value_parser!(u16).range(1..=32))]
+    connections: Option<u16>,
+    /// Seconds to wait for a connection to the server.
+    #[clap(long, value_name = "SECONDS")]
+    connect_timeout: Option<u64>,
+    /// Seconds to wait for the server to answer a request.
+    #[clap(long, value_name = "SECONDS")]
+    read_timeout: Option<u64>,
+    /// Seconds without receiving any data before a download is considered stalled and retried.
+    #[clap(long, value_name = "SECONDS")]
+    stall_timeout: Option<u64>,
+    /// How many times a failed download is retried, resuming from the bytes already written.
+    #[clap(long)]
+    retries: Option<u32>,
+}
+
+impl DownloadArgs {
+    fn apply(self, cfg: &mut config::Config) {
+        if let Some(connections) = self.connections {
+            cfg.connections = connections as usize;
+        }
+        if let Some(connect_timeout) = self.connect_timeout {
+            cfg.connect_timeout = connect_timeout;
+        }
+        if let Some(read_timeout) = self.read_timeout {
+            cfg.read_timeout = read_timeout;
+        }
+        if let Some(stall_timeout) = self.stall_timeout {
+            cfg.stall_timeout = stall_timeout;
+        }
+        if let Some(retries) = self.retries {
+            cfg.retries = retries;
+        }
+    }
+}
+
+#[derive(Args)]
+struct ExtractArgs {
+    /// The most an archive from the source may expand to, e.g. 8GB, larger archives are rejected.
+    #[clap(long, value_name = "SIZE", value_parser = parse_size_arg)]
+    max_extract_size: Option<u64>,
+    /// The most entries an archive from the source may contain.
+    #[clap(long, value_name = "COUNT")]
+    max_extract_entries: Option<u64>,
+}
+
+impl ExtractArgs {
+    /// 修改当前下载源的解压限制
+    fn apply(self, cfg: &mut config::Config) {
+        if let Some(max_size) = self.max_extract_size {
+            cfg.extract_limits_mut().max_size = max_size;
+        }
+        if let Some(max_entries) = self.max_extract_entries {
+            cfg.extract_limits_mut().max_entries = max_entries;
+        }
+    }
+}
+
+#[derive(Clone, PartialEq, ValueEnum)]
+enum BackupInclude {
+    /// The export templates.
+    Templates,
+}
+
+#[derive(Subcommand)]
+enum TemplatesCommands {
+    /// List the installed export templates.
+    #[clap(name = "list", alias = "ls")]
+    List,
+    /// Install the export templates of an engine version.
+    #[clap(name = "install", alias = "i")]
+    Install {
+        /// The engine version, e.g. 4.4.1 or 4.3-stable.
+        version: String,
+        /// Install the C# (mono) export templates.
+        #[clap(long)]
+        mono: bool,
+        #[clap(short, long)]
+        /// Force install.
+        force: bool,
+        #[clap(short = 'k', long)]
+        /// Skip sha512 check.
+        skip_check: bool,
+        #[clap(long, conflicts_with = "skip_check")]
+        /// Download once more if the checksum does not match.
+        redownload: bool,
+    },
+    /// Verify the name, files and matching editor of the installed export templates.
+    #[clap(name = "verify")]
+    Verify,
+    /// Remove the export templates.
+    #[clap(name = "remove", alias = "rm")]
+    Remove {
+        /// The installed export templates to remove.
+        /// 4.4.1.stable.mono
+        name: String,
+    },
+}
+
+#[derive(Subcommand)]
+enum CacheCommands {
+    /// List the cached files.
+    #[clap(name = "list", alias = "ls")]
+    List,
+    /// Remove cached archives that are no longer needed.
+    #[clap(name = "prune")]
+    Prune {
+        /// Remove archives whose engine or templates are installed.
+        #[clap(short, long)]
+        installed: bool,
+        /// Remove archives older than the given number of days.
+        #[clap(short, long, value_name = "DAYS")]
+        older_than: Option<u64>,
+        /// Keep the cache under the given size, e.g. 2GB, removing the oldest archives first.
+        #[clap(short, long, value_name = "SIZE")]
+        max_size: Option<String>,
     },
+    /// Remove all cached files.
+    #[clap(name = "clean")]
+    Clean,
+}
+
+/// 将 `linux`、`arm64` 等参数解析为系统或架构标签
+fn parse_tag<T: Tag>(value: &str) -> Result<T, String> {
+    // x86_64 等写法中不含架构关键字的分隔符，补上以便匹配
+    let keyword = match value.to_lowercase().as_str() {
+        "x86_64" | "amd64" | "64" => "_64".to_string(),
+        "x86_32" | "x86" | "32" => "_32".to_string(),
+        other => other.to_string(),
+    };
+    T::from_keyword(&keyword).ok_or(format!("unknown value: {}", value))
+}
+
+fn parse_size_arg(value: &str) -> Result<u64, String> {
+    parse_size(value).ok_or(format!("invalid size: {}, expected e.g. 8GB", value))
+}
+
+fn parse_date(value: &str) -> Result<String, String> {
+    chrono::NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map(|date| date.format("%Y-%m-%d").to_string())
+        .map_err(|e| format!("{}, expected YYYY-MM-DD", e))
 }
 
 #[tokio::main]
 async fn main() {
     let cli = Cli::parse();
-    match cli.command {
-        Commands::Config { source, proxy } => {
-            let mut cfg = config::Config::init();
-            if let Some(source) = source {
-                cfg.source = Source::from_str(source.as_str());
-            }
-            if let Some(proxy) = proxy {
-                cfg.proxy = proxy;
-            }
-            config::link_appdata(&cfg.data);
-            cfg.save();
-        }
-        Commands::Sync {} => {
-            let cfg = config::Config::init();
-            sync::sync_data(&cfg).await;
-        }
-        Commands::List { remote, version } => {
-            let cfg = config::Config::init();
-            // 如果都为None，则列出所有本地引擎
-            if !remote && version.is_none() {
-                let res = list::list_local_engines(&cfg.home).unwrap();
-                let current = cfg.version.clone();
-                let table = style::show_tree(&res, current.as_ref(), "Local Engines");
-                println!("{}", table);
-            } else if !remote && version.is_some() {
-                let res =
-                    list::list_remote_engine_assets(&cfg.data, version.as_ref().unwrap()).unwrap();
-                let table = style::show_list(&res, "Remote Engine Assets");
-                println!("{}", table);
-            } else if remote && version.is_none() {
-                let res = list::list_remote_engines(&cfg.data).unwrap();
-                let table = style::show_list(&res, "Remote Engines");
-                println!("{}", table);
-            } else {
-                let res =
-                    list::list_remote_engines_major(&cfg.data, version.as_ref().unwrap()).unwrap();
-                let table = style::show_list(&res, "Remote Engines");
-                println!("{}", table);
-            }
+    let format = cli.output;
+    format.apply();
+    let (command, label, result) = match cli.command {
+        Commands::Config {
+            source,
+            proxy,
+            self_contained,
+            download,
+            extract,
+        } => (
+            "config",
+            "Config failed",
+            run_config(source, proxy, self_contained, download, extract),
+        ),
+        Commands::Sync => ("sync", "Sync data failed", run_sync().await),
+        Commands::List {
+            remote,
+            version,
+            all_platforms,
+            major,
+            minor,
+            since,
+            all,
+        } => {
+            let result = since
+                .as_deref()
+                .map(|v| {
+                    parse_version(v)
+                        .ok_or_else(|| GdemError::InvalidInput(format!("Invalid version: {}", v)))
+                })
+                .transpose()
+                .and_then(|since| {
+                    let filter = list::RemoteFilter {
+                        major,
+                        minor,
+                        since,
+                    };
+                    run_list(remote, version.as_deref(), all_platforms, filter, all)
+                });
+            ("list", "List failed", result)
+        }
+        Commands::Search {
+            query,
+            mono,
+            templates,
+            os,
+            arch,
+            channel,
+            after,
+            limit,
+        } => {
+            let options = search::SearchOptions {
+                mono,
+                templates,
+                os,
+                arch,
+                channel,
+                after,
+            };
+            let result = run_search(query.as_deref().unwrap_or_default(), &options, limit);
+            ("search", "Search failed", result)
         }
+        Commands::Notes { range } => ("notes", "Show notes failed", run_notes(&range)),
         Commands::Install {
-            engine,
+            engines,
+            from,
             force,
             skip_check,
+            redownload,
+            with_templates,
+            self_contained,
         } => {
-            let cfg = config::Config::init();
-            match install::full_install_process(&engine, &cfg, force, skip_check)
-                .await
-            {
-                Ok(engine) => {
-                    println!("Install success: {}", engine);
-                }
-                Err(msg) => {
-                    eprintln!("Install failed: {}", msg);
+            let result = match config::Config::init() {
+                Ok(cfg) => {
+                    let options = install::InstallOptions {
+                        force,
+                        skip_check,
+                        self_contained: self_contained || cfg.self_contained,
+                        redownload,
+                    };
+                    match (engines.as_slice(), &from) {
+                        ([engine], None) => {
+                            run_install(engine, &cfg, &options, with_templates).await
+                        }
+                        _ => {
+                            let from = from.as_deref();
+                            run_batch_install(&engines, from, &cfg, &options, with_templates).await
+                        }
+                    }
                 }
+                Err(e) => Err(e),
             };
+            ("install", "Install failed", result)
         }
         Commands::Switch { engine } => {
-            let mut cfg = config::Config::init();
-            match switch::switch_engine(&engine, &mut cfg) {
-                Ok(engine) => {
-                    println!("Switch engine success: {}", engine);
+            let result = config::Config::init()
+                .and_then(|mut cfg| switch::switch_engine(&engine, &mut cfg))
+                .map(|engine| {
+                    let text = format!("Switch engine success: {}", engine);
+                    Output::new(json!({ "engine": engine }), text).with_plain(engine)
+                });
+            ("switch", "Switch engine failed", result)
+        }
+        Commands::Remove { engine, purge_data } => {
+            let result = config::Config::init()
+                .and_then(|mut cfg| remove::remove_engine(&engine, &mut cfg, purge_data))
+                .map(|kept| {
+                    let text = match &kept {
+                        Some(path) => format!(
+                            "Remove engine success, editor data kept in {}",
+                            path.display()
+                        ),
+                        None => "Remove engine success".to_string(),
+                    };
+                    Output::new(json!({ "engine": engine, "editor_data": kept }), text)
+                        .with_plain(engine.clone())
+                });
+            ("remove", "Remove engine failed", result)
+        }
+        Commands::Verify { engine } => {
+            let result = match config::Config::init() {
+                Ok(cfg) => run_verify(engine.as_deref(), &cfg).await,
+                Err(e) => Err(e),
+            };
+            ("verify", "Verify failed", result)
+        }
+        Commands::Cache { command } => match config::Config::init() {
+            Err(e) => ("cache", "Load config failed", Err(e)),
+            Ok(cfg) => match command {
+                CacheCommands::List => ("cache list", "List cache failed", run_cache_list(&cfg)),
+                CacheCommands::Prune {
+                    installed,
+                    older_than,
+                    max_size,
+                } => {
+                    let result = run_cache_prune(&cfg, installed, older_than, max_size);
+                    ("cache prune", "Prune cache failed", result)
+                }
+                CacheCommands::Clean => {
+                    let result = cache::clean_cache(&cfg.cache).map(|freed| {
+                        let text =
+                            format!("Clean cache success, freed {}", format_size(freed as f64));
+                        Output::new(json!({ "freed": freed }), text).with_plain(freed.to_string())
+                    });
+                    ("cache clean", "Clean cache failed", result)
+                }
+            },
+        },
+        Commands::Templates { command } => match config::Config::init() {
+            Err(e) => ("templates", "Load config failed", Err(e)),
+            Ok(cfg) => match command {
+                TemplatesCommands::List => (
+                    "templates list",
+                    "List templates failed",
+                    run_templates_list(&cfg),
+                ),
+                TemplatesCommands::Install {
+                    version,
+                    mono,
+                    force,
+                    skip_check,
+                    redownload,
+                } => {
+                    let options = install::InstallOptions {
+                        force,
+                        skip_check,
+                        redownload,
+                        ..Default::default()
+                    };
+                    let result = run_templates_install(&version, mono, &cfg, &options).await;
+                    ("templates install", "Install templates failed", result)
+                }
+                TemplatesCommands::Verify => (
+                    "templates verify",
+                    "Verify templates failed",
+                    run_templates_verify(&cfg),
+                ),
+                TemplatesCommands::Remove { name } => {
+                    let result = templates::remove_templates(&name, &cfg).map(|_| {
+                        Output::new(json!({ "name": name }), "Remove templates success")
+                            .with_plain(name.clone())
+                    });
+                    ("templates remove", "Remove templates failed", result)
+                }
+            },
+        },
+        Commands::Data { command } => match config::Config::init() {
+            Err(e) => ("data", "Load config failed", Err(e)),
+            Ok(mut cfg) => match command {
+                DataCommands::Status => (
+                    "data status",
+                    "Show data status failed",
+                    run_data_status(&cfg),
+                ),
+                DataCommands::Link => {
+                    let result = config::link_appdata(&cfg.data).and_then(|_| {
+                        cfg.link_data = true;
+                        cfg.save()?;
+                        Ok(Output::new(json!({ "linked": true }), "Link data success")
+                            .with_plain(""))
+                    });
+                    ("data link", "Link data failed", result)
                 }
-                Err(msg) => {
-                    eprintln!("Switch engine failed: {}", msg);
+                DataCommands::Unlink => (
+                    "data unlink",
+                    "Unlink data failed",
+                    run_data_unlink(&mut cfg),
+                ),
+                DataCommands::Backup { include } => {
+                    let include_templates = include.contains(&BackupInclude::Templates);
+                    let result = run_data_backup(&cfg, include_templates);
+                    ("data backup", "Backup data failed", result)
                 }
+                DataCommands::Restore { archive, yes } => {
+                    let result = run_data_restore(&cfg, &archive, yes, format);
+                    ("data restore", "Restore data failed", result)
+                }
+            },
+        },
+    };
+    let code = output::emit(format, command, label, result);
+    if code != 0 {
+        std::process::exit(code);
+    }
+}
+
+fn run_config(
+    source: Option<String>,
+    proxy: Option<String>,
+    self_contained: Option<bool>,
+    download: DownloadArgs,
+    extract: ExtractArgs,
+) -> CommandResult {
+    let mut cfg = config::Config::init()?;
+    if let Some(source) = source {
+        cfg.source = Source::from_name(source.as_str());
+    }
+    if let Some(proxy) = proxy {
+        cfg.proxy = proxy;
+    }
+    download.apply(&mut cfg);
+    // 限制属于当前下载源，与 --source 一起使用时修改新的下载源
+    extract.apply(&mut cfg);
+    if let Some(self_contained) = self_contained {
+        cfg.self_contained = self_contained;
+        if !cfg.version.is_empty() {
+            let engine_dir = get_engine_dir(&cfg.home, &cfg.version)?;
+            if let Err(msg) = set_self_contained(&engine_dir, self_contained) {
+                eprintln!("Set self-contained mode failed: {}", msg);
+            }
+        }
+    }
+    if cfg.link_data
+        && let Err(msg) = config::link_appdata(&cfg.data)
+    {
+        eprintln!("Link Godot data failed: {}", msg);
+    }
+    cfg.save()?;
+    Ok(Output::new(serde_json::to_value(&cfg)?, "").with_plain(""))
+}
+
+async fn run_sync() -> CommandResult {
+    let cfg = config::Config::init()?;
+    let file = cfg.data.join("releases.json");
+    let synced = sync::sync_data(&cfg).await?;
+    let text = if synced {
+        String::new()
+    } else {
+        format!("Data already synced: {}", file.display())
+    };
+    let data = json!({ "file": file, "synced": synced });
+    Ok(Output::new(data, text).with_plain(file.display().to_string()))
+}
+
+fn run_list(
+    remote: bool,
+    version: Option<&str>,
+    all_platforms: bool,
+    filter: list::RemoteFilter,
+    all: bool,
+) -> CommandResult {
+    let cfg = config::Config::init()?;
+    // 使用筛选条件时视为列出远程引擎
+    let remote = remote || all || !filter.is_empty();
+    match (remote, version) {
+        // 如果都为None，则列出所有本地引擎
+        (false, None) => {
+            let res = list::list_local_engines(&cfg.home)?;
+            let current = cfg.version.clone();
+            let engines: Vec<_> = res
+                .iter()
+                .map(|name| json!({ "name": name, "current": *name == current }))
+                .collect();
+            let data = json!({ "current": current, "engines": engines });
+            let text = style::show_tree(&res, current.as_ref(), "Local Engines");
+            Ok(Output::new(data, text).with_plain(res.join("\n")))
+        }
+        (false, Some(version)) => {
+            let res = list::list_remote_engine_assets(&cfg.data, version, all_platforms)?;
+            // (已安装, 已缓存)
+            let states: Vec<(bool, bool)> = res
+                .iter()
+                .map(|a| {
+                    (
+                        cache::is_installed(&a.name, &cfg),
+                        cache::is_cached(&a.name, &cfg),
+                    )
+                })
+                .collect();
+            let rows: Vec<Vec<String>> = res
+                .iter()
+                .zip(&states)
+                .map(|(a, (installed, cached))| {
+                    let state = match (installed, cached) {
+                        (true, _) => "installed",
+                        (false, true) => "cached",
+                        (false, false) => "",
+                    };
+                    vec![
+                        a.name.clone(),
+                        a.kind().get_labels().to_string(),
+                        a.get_flavor().to_string(),
+                        a.get_platform(),
+                        format_size(a.size as f64),
+                        a.get_date().to_string(),
+                        state.to_string(),
+                    ]
+                })
+                .collect();
+            let headers = [
+                "Name", "Kind", "Flavor", "Platform", "Size", "Date", "Status",
+            ];
+            let text = style::show_table(&headers, &rows, "Remote Engine Assets");
+            let assets: Vec<_> = res
+                .iter()
+                .zip(&states)
+                .map(|(a, (installed, cached))| {
+                    json!({
+                        "name": a.name,
+                        "kind": a.kind().get_labels(),
+                        "mono": a.is_mono(),
+                        "os": a.os().map(|os| os.get_labels()),
+                        "arch": a.arch().map(|arch| arch.get_labels()),
+                        "size": a.size,
+                        "date": a.get_date(),
+                        "url": a.browser_download_url,
+                        "cached": cached,
+                        "installed": installed,
+                    })
+                })
+                .collect();
+            let data = json!({ "version": version, "assets": assets });
+            Ok(Output::new(data, text).with_plain_rows(&rows))
+        }
+        (true, version) => run_list_remote(&cfg, version, filter, all),
+    }
+}
+
+fn run_list_remote(
+    cfg: &config::Config,
+    version: Option<&str>,
+    mut filter: list::RemoteFilter,
+    all: bool,
+) -> CommandResult {
+    // -v 3 或 -v 4.3 等同于 --major 3 或 --major 4 --minor 3
+    if let Some(version) = version {
+        let major = list::RemoteFilter::from_version(version)
+            .ok_or_else(|| GdemError::InvalidInput(format!("Invalid version: {}", version)))?;
+        filter.major = major.major;
+        filter.minor = major.minor.or(filter.minor);
+    }
+    let versions = if filter.is_empty() && !all {
+        list::list_remote_engines(&cfg.data)?
+    } else {
+        list::list_remote_versions(&cfg.data, &filter)?
+    };
+    let installed = list::get_installed_tags(&cfg.home)?;
+    let current = extract_tag(&cfg.version).unwrap_or_default();
+
+    let mut data = json!({
+        "versions": versions,
+        "installed": installed,
+        "current": current,
+    });
+    let text = if all {
+        let groups = list::group_versions(&versions);
+        let nodes: Vec<style::TreeNode> = groups
+            .iter()
+            .map(|group| {
+                let minors = group
+                    .minors
+                    .iter()
+                    .map(|(minor, tags)| {
+                        let leaves = tags
+                            .iter()
+                            .map(|tag| {
+                                let label = style::mark_version(
+                                    tag,
+                                    installed.contains(tag),
+                                    *tag == current,
+                                );
+                                style::TreeNode::new(label, Vec::new())
+                            })
+                            .collect();
+                        style::TreeNode::new(minor.clone(), leaves)
+                    })
+                    .collect();
+                style::TreeNode::new(group.name.clone(), minors)
+            })
+            .collect();
+        data["groups"] = groups
+            .iter()
+            .map(|group| {
+                let minors: Vec<_> = group
+                    .minors
+                    .iter()
+                    .map(|(minor, tags)| json!({ "minor": minor, "versions": tags }))
+                    .collect();
+                json!({ "major": group.name, "minors": minors })
+            })
+            .collect();
+        style::show_nested_tree(&nodes, "Remote Engines")
+    } else {
+        style::show_marked_list(&versions, &installed, &current, "Remote Engines")
+    };
+    Ok(Output::new(data, text).with_plain(versions.join("\n")))
+}
+
+fn run_search(query: &str, options: &search::SearchOptions, limit: usize) -> CommandResult {
+    let cfg = config::Config::init()?;
+    let mut hits = search::search_catalog(&cfg.data, query, options)?;
+    let total = hits.len();
+    hits.truncate(limit);
+    let rows: Vec<Vec<String>> = hits
+        .iter()
+        .map(|hit| {
+            vec![
+                hit.asset.name.clone(),
+                hit.version.clone(),
+                hit.asset.kind().get_labels().to_string(),
+                hit.asset.get_flavor().to_string(),
+                hit.asset.get_platform(),
+                format_size(hit.asset.size as f64),
+            ]
+        })
+        .collect();
+    let headers = ["Name", "Version", "Kind", "Flavor", "Platform", "Size"];
+    let mut text = style::show_table(&headers, &rows, "Search Results");
+    if total > hits.len() {
+        text.push_str(&format!(
+            "\n{} of {} results, use --limit to show more",
+            hits.len(),
+            total
+        ));
+    }
+    // 其他系统和架构的编辑器只能下载到本机以外使用，install 无法安装
+    let foreign = hits.iter().filter(|hit| !hit.asset.is_local_platform()).count();
+    if foreign > 0 {
+        text.push_str(&format!(
+            "\n{} editor(s) are for another system or architecture and cannot be installed here",
+            foreign
+        ));
+    }
+    let results: Vec<_> = hits
+        .iter()
+        .map(|hit| {
+            json!({
+                "name": hit.asset.name,
+                "version": hit.version,
+                "kind": hit.asset.kind().get_labels(),
+                "mono": hit.asset.is_mono(),
+                "os": hit.asset.os().map(|os| os.get_labels()),
+                "arch": hit.asset.arch().map(|arch| arch.get_labels()),
+                "size": hit.asset.size,
+                "score": hit.score,
+                "installable": hit.asset.is_local_platform(),
+            })
+        })
+        .collect();
+    let names: Vec<String> = hits
+        .iter()
+        .filter(|hit| hit.asset.is_local_platform())
+        .map(|hit| hit.asset.name.clone())
+        .collect();
+    let data = json!({ "query": query, "total": total, "results": results });
+    // plain 模式每行一个文件名，可以直接传给 install，因此不包含其他平台的编辑器
+    Ok(Output::new(data, text).with_plain(names.join("\n")))
+}
+
+fn run_notes(range: &str) -> CommandResult {
+    let cfg = config::Config::init()?;
+    let releases = notes::get_release_notes(&cfg.data, range)?;
+    let sections: Vec<String> = releases
+        .iter()
+        .map(|r| {
+            let title = format!("{} ({})", r.tag_name, r.get_date());
+            let body = if r.body.trim().is_empty() {
+                "No release notes".truecolor(128, 128, 128).to_string()
+            } else {
+                style::render_markdown(&r.body)
             };
+            format!("{}\n{}", title.green().bold(), body)
+        })
+        .collect();
+    let text = if sections.is_empty() {
+        format!("No releases in {}", range)
+    } else {
+        sections.join("\n\n")
+    };
+    let items: Vec<_> = releases
+        .iter()
+        .map(|r| json!({ "version": r.tag_name, "date": r.get_date(), "body": r.body }))
+        .collect();
+    let plain: Vec<String> = releases
+        .iter()
+        .map(|r| format!("{}\n{}", r.tag_name, r.body.trim()))
+        .collect();
+    let data = json!({ "range": range, "releases": items });
+    Ok(Output::new(data, text).with_plain(plain.join("\n\n")))
+}
+
+async fn run_install(
+    engine: &str,
+    cfg: &config::Config,
+    options: &install::InstallOptions,
+    with_templates: bool,
+) -> CommandResult {
+    let engine = install::full_install_process(engine, cfg, options).await?;
+    let mut lines = vec![format!("Install success: {}", engine)];
+    let mut plain = vec![engine.clone()];
+    let mut data = json!({ "engine": engine, "templates": null });
+    let mut code = 0;
+    if with_templates {
+        match install::install_engine_templates(&engine, cfg, options).await {
+            Ok(templates) => {
+                lines.push(format!("Install templates success: {}", templates));
+                plain.push(templates.clone());
+                data["templates"] = json!({ "ok": true, "name": templates });
+            }
+            Err(e) => {
+                // 引擎已经安装成功，与批量安装相同，以模板的错误类型作为退出码
+                lines.push(format!("Install templates failed: {}", e));
+                data["templates"] = json!({
+                    "ok": false,
+                    "error": { "code": e.code(), "message": e.to_string() },
+                });
+                code = e.exit_code();
+            }
         }
-        Commands::Remove { engine } => {
-            let mut cfg = config::Config::init();
-            match remove::remove_engine(&engine, &mut cfg) {
-                Ok(_) => {
-                    println!("Remove engine success");
-                }
-                Err(msg) => {
-                    eprintln!("Remove engine failed: {}", msg);
+    }
+    Ok(Output::new(data, lines.join("\n"))
+        .with_plain(plain.join("\n"))
+        .with_exit_code(code))
+}
+
+async fn run_batch_install(
+    engines: &[String],
+    from: Option<&Path>,
+    cfg: &config::Config,
+    options: &install::InstallOptions,
+    with_templates: bool,
+) -> CommandResult {
+    let mut specs: Vec<batch::InstallSpec> = engines
+        .iter()
+        .cloned()
+        .map(batch::InstallSpec::Engine)
+        .collect();
+    if let Some(path) = from {
+        specs.extend(batch::load_install_manifest(path)?.specs());
+    }
+    let items = batch::batch_install(&specs, with_templates, cfg, options).await?;
+
+    let rows: Vec<Vec<String>> = items
+        .iter()
+        .map(|item| {
+            let (result, detail) = match &item.result {
+                Ok(name) => ("ok", name.clone()),
+                // 表格中只显示错误的第一行，完整的信息在 JSON 中
+                Err(e) => {
+                    let message = e.to_string();
+                    let first = message.lines().next().unwrap_or_default().to_string();
+                    ("failed", first)
                 }
             };
+            vec![
+                item.spec.clone(),
+                item.asset.clone(),
+                result.to_string(),
+                detail,
+            ]
+        })
+        .collect();
+    let failed: Vec<&GdemError> = items
+        .iter()
+        .filter_map(|i| i.result.as_ref().err())
+        .collect();
+    let title = format!(
+        "Install Summary ({} installed, {} failed)",
+        items.len() - failed.len(),
+        failed.len()
+    );
+    let headers = ["Spec", "Asset", "Result", "Detail"];
+    let text = style::show_table(&headers, &rows, &title);
+    let data_items: Vec<_> = items
+        .iter()
+        .map(|item| match &item.result {
+            Ok(name) => json!({
+                "spec": item.spec,
+                "asset": item.asset,
+                "ok": true,
+                "name": name,
+            }),
+            Err(e) => json!({
+                "spec": item.spec,
+                "asset": item.asset,
+                "ok": false,
+                "error": { "code": e.code(), "message": e.to_string() },
+            }),
+        })
+        .collect();
+    // 有文件安装失败时，以第一个失败的错误类型作为退出码
+    let code = failed.first().map_or(0, |e| e.exit_code());
+    Ok(Output::new(json!({ "items": data_items }), text)
+        .with_plain_rows(&rows)
+        .with_exit_code(code))
+}
+
+async fn run_verify(engine: Option<&str>, cfg: &config::Config) -> CommandResult {
+    let checks = checksum::verify_cache(engine, cfg).await?;
+    let rows: Vec<Vec<String>> = checks
+        .iter()
+        .map(|c| {
+            let algorithm = c
+                .expected
+                .as_ref()
+                .map_or("-".to_string(), |d| d.algorithm.to_string());
+            let signature = match &c.signature {
+                Some(checksum::Signature::Verified) => "verified".to_string(),
+                Some(checksum::Signature::Unchecked(reason)) => format!("unchecked ({})", reason),
+                None => "-".to_string(),
+            };
+            let result = match c.is_ok() {
+                Some(true) => "ok",
+                Some(false) => "mismatch",
+                None => "not listed",
+            };
+            vec![
+                c.entry.name.clone(),
+                c.entry.version.clone(),
+                algorithm,
+                signature,
+                result.to_string(),
+            ]
+        })
+        .collect();
+    let headers = ["Name", "Version", "Checksum", "Signature", "Result"];
+    let text = style::show_table(&headers, &rows, "Verify Cache");
+    let items: Vec<_> = checks
+        .iter()
+        .map(|c| {
+            json!({
+                "name": c.entry.name,
+                "version": c.entry.version,
+                "path": c.entry.path,
+                "algorithm": c.expected.as_ref().map(|d| d.algorithm),
+                "expected": c.expected.as_ref().map(|d| &d.value),
+                "actual": c.actual,
+                "signature": c.signature,
+                "ok": c.is_ok(),
+            })
+        })
+        .collect();
+    Ok(Output::new(json!({ "checks": items }), text).with_plain_rows(&rows))
+}
+
+fn run_cache_list(cfg: &config::Config) -> CommandResult {
+    let entries = cache::list_cache(cfg)?;
+    let (partial, complete): (Vec<_>, Vec<_>) = entries.into_iter().partition(|e| e.partial);
+    let total: u64 = complete.iter().chain(partial.iter()).map(|e| e.size).sum();
+    let rows: Vec<Vec<String>> = complete
+        .iter()
+        .map(|e| {
+            let status = if e.installed { "installed" } else { "" };
+            vec![
+                e.name.clone(),
+                e.version.clone(),
+                format_size(e.size as f64),
+                cache::format_age(e.age_days()),
+                status.to_string(),
+            ]
+        })
+        .collect();
+    let headers = ["Name", "Version", "Size", "Age", "Status"];
+    let mut text = style::show_table(&headers, &rows, "Cached Files");
+    if !partial.is_empty() {
+        let rows: Vec<Vec<String>> = partial
+            .iter()
+            .map(|e| {
+                vec![
+                    e.name.clone(),
+                    e.version.clone(),
+                    format_size(e.size as f64),
+                    cache::format_age(e.age_days()),
+                ]
+            })
+            .collect();
+        let headers = ["Name", "Version", "Downloaded", "Age"];
+        text.push('\n');
+        text.push_str(&style::show_table(&headers, &rows, "Partial Downloads"));
+    }
+    text.push_str(&format!("\nTotal: {}", format_size(total as f64)));
+
+    let files: Vec<_> = complete
+        .iter()
+        .chain(partial.iter())
+        .map(|e| {
+            json!({
+                "name": e.name,
+                "version": e.version,
+                "path": e.path,
+                "size": e.size,
+                "age_days": e.age_days(),
+                "installed": e.installed,
+                "partial": e.partial,
+            })
+        })
+        .collect();
+    let plain: Vec<Vec<String>> = complete
+        .iter()
+        .chain(partial.iter())
+        .map(|e| {
+            let status = match (e.partial, e.installed) {
+                (true, _) => "partial",
+                (false, true) => "installed",
+                (false, false) => "cached",
+            };
+            vec![
+                e.name.clone(),
+                e.version.clone(),
+                e.size.to_string(),
+                status.to_string(),
+            ]
+        })
+        .collect();
+    let data = json!({ "files": files, "total": total });
+    Ok(Output::new(data, text).with_plain_rows(&plain))
+}
+
+fn run_cache_prune(
+    cfg: &config::Config,
+    installed: bool,
+    older_than: Option<u64>,
+    max_size: Option<String>,
+) -> CommandResult {
+    let max_size = match max_size {
+        Some(size) => Some(
+            parse_size(&size)
+                .ok_or_else(|| GdemError::InvalidInput(format!("Invalid size: {}", size)))?,
+        ),
+        None => None,
+    };
+    let options = cache::PruneOptions {
+        installed,
+        older_than,
+        max_size,
+    };
+    if options.is_empty() {
+        return Err(GdemError::InvalidInput(
+            "Nothing to prune, use --installed, --older-than or --max-size".to_string(),
+        ));
+    }
+    let removed = cache::prune_cache(cfg, &options)?;
+    let freed: u64 = removed.iter().map(|e| e.size).sum();
+    let names: Vec<String> = removed.iter().map(|e| e.name.clone()).collect();
+    let text = format!(
+        "{}\nPrune cache success, freed {}",
+        style::show_list(&names, "Removed Files"),
+        format_size(freed as f64)
+    );
+    let data = json!({ "removed": names, "freed": freed });
+    Ok(Output::new(data, text).with_plain(names.join("\n")))
+}
+
+fn run_templates_list(cfg: &config::Config) -> CommandResult {
+    let sets = templates::list_templates(cfg)?;
+    let rows: Vec<Vec<String>> = sets
+        .iter()
+        .map(|t| {
+            let status = if t.is_orphaned() { "orphaned" } else { "" };
+            vec![
+                t.name.clone(),
+                t.version.clone().unwrap_or("-".to_string()),
+                t.engines.join(", "),
+                status.to_string(),
+            ]
+        })
+        .collect();
+    let headers = ["Name", "Version", "Engines", "Status"];
+    let text = style::show_table(&headers, &rows, "Export Templates");
+    let items: Vec<_> = sets
+        .iter()
+        .map(|t| {
+            json!({
+                "name": t.name,
+                "path": t.path,
+                "version": t.version,
+                "engines": t.engines,
+                "owner": t.owner,
+                "orphaned": t.is_orphaned(),
+            })
+        })
+        .collect();
+    Ok(Output::new(json!({ "templates": items }), text).with_plain_rows(&rows))
+}
+
+async fn run_templates_install(
+    version: &str,
+    mono: bool,
+    cfg: &config::Config,
+    options: &install::InstallOptions,
+) -> CommandResult {
+    let asset = templates::query_template_asset(version, mono, &cfg.data)?;
+    let name = install::full_install_process(&asset, cfg, options).await?;
+    let text = format!("Install templates success: {}", name);
+    Ok(Output::new(json!({ "templates": name }), text).with_plain(name))
+}
+
+fn run_templates_verify(cfg: &config::Config) -> CommandResult {
+    let checks = templates::verify_templates(cfg)?;
+    let rows: Vec<Vec<String>> = checks
+        .iter()
+        .map(|c| {
+            let name = if c.name_matched {
+                "ok".to_string()
+            } else {
+                format!("mismatch ({})", c.set.version.as_deref().unwrap_or("-"))
+            };
+            let files = match c.missing.len() {
+                0 => "ok".to_string(),
+                1..=3 => format!("missing {}", c.missing.join(", ")),
+                n => format!("missing {} files", n),
+            };
+            let editor = if c.set.is_orphaned() {
+                "none".to_string()
+            } else {
+                c.set.engines.join(", ")
+            };
+            let result = if c.is_ok() { "ok" } else { "failed" };
+            vec![c.set.name.clone(), name, files, editor, result.to_string()]
+        })
+        .collect();
+    let headers = ["Name", "Version", "Files", "Editor", "Result"];
+    let text = style::show_table(&headers, &rows, "Verify Templates");
+    let items: Vec<_> = checks
+        .iter()
+        .map(|c| {
+            json!({
+                "name": c.set.name,
+                "version": c.set.version,
+                "name_matched": c.name_matched,
+                "missing": c.missing,
+                "engines": c.set.engines,
+                "ok": c.is_ok(),
+            })
+        })
+        .collect();
+    Ok(Output::new(json!({ "checks": items }), text).with_plain_rows(&rows))
+}
+
+fn run_data_status(cfg: &config::Config) -> CommandResult {
+    let status = data::data_status(&cfg.data)?;
+    let rows: Vec<Vec<String>> = status
+        .iter()
+        .map(|s| {
+            let location = s
+                .location
+                .as_ref()
+                .map(|p| p.display().to_string())
+                .unwrap_or("-".to_string());
+            vec![
+                s.dir.kind.to_string(),
+                s.dir.system.display().to_string(),
+                s.state.get_labels(),
+                location,
+                format_size(s.size as f64),
+            ]
+        })
+        .collect();
+    let headers = ["Kind", "Path", "State", "Location", "Size"];
+    let text = style::show_table(&headers, &rows, "Godot Data");
+    let items: Vec<_> = status
+        .iter()
+        .map(|s| {
+            json!({
+                "kind": s.dir.kind,
+                "path": s.dir.system,
+                "managed": s.dir.managed,
+                "state": s.state.get_labels(),
+                "location": s.location,
+                "size": s.size,
+            })
+        })
+        .collect();
+    Ok(Output::new(json!({ "dirs": items }), text).with_plain_rows(&rows))
+}
+
+fn run_data_unlink(cfg: &mut config::Config) -> CommandResult {
+    let dirs = data::unlink_data(&cfg.data)?;
+    cfg.link_data = false;
+    cfg.save()?;
+    let names: Vec<String> = dirs
+        .iter()
+        .map(|d| d.system.display().to_string())
+        .collect();
+    let text = format!(
+        "{}\nUnlink data success, a copy is kept in {}",
+        style::show_list(&names, "Unlinked Directories"),
+        cfg.data.display()
+    );
+    let data = json!({ "unlinked": names, "copy": cfg.data });
+    Ok(Output::new(data, text).with_plain(names.join("\n")))
+}
+
+fn run_data_backup(cfg: &config::Config, include_templates: bool) -> CommandResult {
+    let backup_dir = cfg.root.join("backups");
+    let (backup_file, names) = data::backup_data(&cfg.data, &backup_dir, include_templates)?;
+    let text = format!(
+        "{}\nBackup data success: {}",
+        style::show_list(&names, "Backup Data"),
+        backup_file.display()
+    );
+    let data = json!({ "archive": backup_file, "items": names });
+    Ok(Output::new(data, text).with_plain(backup_file.display().to_string()))
+}
+
+fn run_data_restore(
+    cfg: &config::Config,
+    archive: &PathBuf,
+    yes: bool,
+    format: OutputFormat,
+) -> CommandResult {
+    let items = data::preview_restore(archive, &cfg.data)?;
+    let rows: Vec<Vec<String>> = items
+        .iter()
+        .map(|i| {
+            let action = if i.exists { "replace" } else { "new" };
+            vec![
+                i.name.clone(),
+                action.to_string(),
+                i.target.display().to_string(),
+            ]
+        })
+        .collect();
+    if format == OutputFormat::Text {
+        let headers = ["Name", "Action", "Target"];
+        println!("{}", style::show_table(&headers, &rows, "Restore Data"));
+    }
+    if !yes {
+        // 非文本模式下无法交互确认
+        if format != OutputFormat::Text {
+            return Err(GdemError::InvalidInput(
+                "Confirmation required, use --yes to restore".to_string(),
+            ));
+        }
+        print!("Continue? [y/N] ");
+        io::stdout().flush().ok();
+        let mut answer = String::new();
+        io::stdin().read_line(&mut answer).ok();
+        if !answer.trim().eq_ignore_ascii_case("y") {
+            let data = json!({ "archive": archive, "restored": false });
+            return Ok(Output::new(data, "Restore data cancelled").with_plain(""));
         }
     }
+    data::restore_data(archive, &cfg.data)?;
+    let items: Vec<_> = items
+        .iter()
+        .map(|i| json!({ "name": i.name, "target": i.target, "replaced": i.exists }))
+        .collect();
+    let data = json!({ "archive": archive, "restored": true, "items": items });
+    Ok(Output::new(data, "Restore data success").with_plain_rows(&rows))
 }
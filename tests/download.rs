@@ -0,0 +1,64 @@
+//! 使用本地 HTTP 服务器测试下载时对服务器响应的处理
+
+use gdem::core::download::{DownloadOptions, download_file};
+use std::fs;
+use std::io::{BufRead, BufReader, Write};
+use std::net::TcpListener;
+use std::thread;
+
+/// 启动只处理 GET 请求的服务器，带 Range 时返回 `bytes 0-0/*`，即不知道文件大小的 206
+fn serve_unknown_size_ranges(body: &'static [u8]) -> String {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut ranged = false;
+            let mut reader = BufReader::new(&stream);
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                if line.trim().is_empty() {
+                    break;
+                }
+                ranged |= line.to_ascii_lowercase().starts_with("range:");
+            }
+            let (status, range, data) = if ranged {
+                (
+                    "206 Partial Content",
+                    "Content-Range: bytes 0-0/*\r\n",
+                    &body[..1],
+                )
+            } else {
+                ("200 OK", "", body)
+            };
+            let head = format!(
+                "HTTP/1.1 {}\r\n{}Content-Length: {}\r\nConnection: close\r\n\r\n",
+                status,
+                range,
+                data.len()
+            );
+            stream.write_all(head.as_bytes()).unwrap();
+            stream.write_all(data).unwrap();
+        }
+    });
+    format!("http://{}/Godot.zip", addr)
+}
+
+#[tokio::test]
+async fn partial_probe_without_size_downloads_the_whole_file() {
+    let body: &'static [u8] = b"the whole file, not only the first byte";
+    let uri = serve_unknown_size_ranges(body);
+    let dir = std::env::temp_dir().join(format!("gdem-download-{}", std::process::id()));
+    fs::create_dir_all(&dir).unwrap();
+    let file_path = dir.join("Godot.zip");
+    let _ = fs::remove_file(&file_path);
+
+    let options = DownloadOptions {
+        retries: 0,
+        ..Default::default()
+    };
+    download_file(&uri, &file_path, &options).await.unwrap();
+    assert_eq!(fs::read(&file_path).unwrap(), body);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,371 @@
+//! 使用构造的压缩包测试解压时对路径穿越、符号链接和压缩炸弹的防护
+
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use gdem::core::error::GdemError;
+use gdem::core::utils::{ExtractLimits, extract_tar_gz, extract_zip};
+use gdem::func::tool::{find_engine_executable, get_tpz_template_dir};
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use tar::{Builder, EntryType, Header};
+use zip::ZipWriter;
+use zip::write::SimpleFileOptions;
+
+/// 每个测试使用独立的临时目录，`archive` 存放压缩包，`out` 为解压目录
+struct Sandbox {
+    root: PathBuf,
+}
+
+impl Sandbox {
+    fn new(name: &str) -> Self {
+        let root =
+            std::env::temp_dir().join(format!("gdem-extract-{}-{}", std::process::id(), name));
+        if root.exists() {
+            fs::remove_dir_all(&root).unwrap();
+        }
+        fs::create_dir_all(root.join("out")).unwrap();
+        Self { root }
+    }
+
+    fn archive(&self, name: &str) -> PathBuf {
+        self.root.join(name)
+    }
+
+    fn out(&self) -> PathBuf {
+        self.root.join("out")
+    }
+}
+
+impl Drop for Sandbox {
+    fn drop(&mut self) {
+        let _ = fs::remove_dir_all(&self.root);
+    }
+}
+
+enum Item<'a> {
+    File(&'a str, &'a [u8]),
+    Symlink(&'a str, &'a str),
+}
+
+fn write_zip(path: &Path, items: &[Item]) {
+    let mut zip = ZipWriter::new(File::create(path).unwrap());
+    let options = SimpleFileOptions::default().unix_permissions(0o644);
+    for item in items {
+        match item {
+            Item::File(name, data) => {
+                zip.start_file(*name, options).unwrap();
+                zip.write_all(data).unwrap();
+            }
+            Item::Symlink(name, target) => zip.add_symlink(*name, *target, options).unwrap(),
+        }
+    }
+    zip.finish().unwrap();
+}
+
+/// 直接写入头部的名称字段，`Header::set_path` 会拒绝不安全的路径
+fn raw_header(name: &str, kind: EntryType, size: u64) -> Header {
+    let mut header = Header::new_gnu();
+    header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+    header.set_entry_type(kind);
+    header.set_size(size);
+    header.set_mode(0o644);
+    header
+}
+
+fn write_tar_gz(path: &Path, items: &[Item]) {
+    let encoder = GzEncoder::new(File::create(path).unwrap(), Compression::default());
+    let mut builder = Builder::new(encoder);
+    for item in items {
+        match item {
+            Item::File(name, data) => {
+                let mut header = raw_header(name, EntryType::Regular, data.len() as u64);
+                header.set_cksum();
+                builder.append(&header, *data).unwrap();
+            }
+            Item::Symlink(name, target) => {
+                let mut header = raw_header(name, EntryType::Symlink, 0);
+                header.as_old_mut().linkname[..target.len()].copy_from_slice(target.as_bytes());
+                header.set_cksum();
+                builder.append(&header, &[][..]).unwrap();
+            }
+        }
+    }
+    builder.into_inner().unwrap().finish().unwrap();
+}
+
+fn assert_unsafe(result: Result<impl std::fmt::Debug, GdemError>) {
+    match result {
+        Err(GdemError::UnsafeArchive(_)) => {}
+        other => panic!("expected an unsafe archive error, got {:?}", other),
+    }
+}
+
+#[test]
+fn zip_extracts_regular_entries() {
+    let sandbox = Sandbox::new("zip-ok");
+    let archive = sandbox.archive("ok.zip");
+    write_zip(
+        &archive,
+        &[
+            Item::File("a/readme.txt", b"hello"),
+            Item::File("b.txt", b"b"),
+        ],
+    );
+    let report = extract_zip(&archive, &sandbox.out(), &ExtractLimits::default()).unwrap();
+    assert_eq!(report.extracted, 2);
+    assert_eq!(
+        fs::read(sandbox.out().join("a/readme.txt")).unwrap(),
+        b"hello"
+    );
+}
+
+#[test]
+fn zip_keeps_a_single_app_bundle() {
+    let sandbox = Sandbox::new("zip-app");
+    let archive = sandbox.archive("macos.zip");
+    write_zip(
+        &archive,
+        &[
+            Item::File("Godot.app/Contents/Info.plist", b"plist"),
+            Item::File("Godot.app/Contents/MacOS/Godot", b"godot"),
+        ],
+    );
+    extract_zip(&archive, &sandbox.out(), &ExtractLimits::default()).unwrap();
+    assert_eq!(
+        find_engine_executable(&sandbox.out()),
+        Some(sandbox.out().join("Godot.app/Contents/MacOS/Godot"))
+    );
+}
+
+#[test]
+fn zip_rejects_parent_components() {
+    let sandbox = Sandbox::new("zip-parent");
+    let archive = sandbox.archive("parent.zip");
+    write_zip(&archive, &[Item::File("../evil.txt", b"bad")]);
+    assert_unsafe(extract_zip(
+        &archive,
+        &sandbox.out(),
+        &ExtractLimits::default(),
+    ));
+    assert!(!sandbox.root.join("evil.txt").exists());
+}
+
+#[test]
+fn zip_rejects_absolute_paths() {
+    let sandbox = Sandbox::new("zip-absolute");
+    let archive = sandbox.archive("absolute.zip");
+    let target = sandbox.root.join("absolute.txt");
+    write_zip(&archive, &[Item::File(target.to_str().unwrap(), b"bad")]);
+    assert_unsafe(extract_zip(
+        &archive,
+        &sandbox.out(),
+        &ExtractLimits::default(),
+    ));
+    assert!(!target.exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn zip_keeps_symlinks_inside_the_target() {
+    let sandbox = Sandbox::new("zip-link-ok");
+    let archive = sandbox.archive("link.zip");
+    write_zip(
+        &archive,
+        &[
+            Item::File("readme.txt", b"readme"),
+            Item::File("lib/libreal.so.1", b"lib"),
+            Item::Symlink("lib/libreal.so", "libreal.so.1"),
+        ],
+    );
+    extract_zip(&archive, &sandbox.out(), &ExtractLimits::default()).unwrap();
+    let link = sandbox.out().join("lib/libreal.so");
+    assert!(link.symlink_metadata().unwrap().is_symlink());
+    assert_eq!(fs::read(link).unwrap(), b"lib");
+}
+
+#[cfg(unix)]
+#[test]
+fn zip_rejects_symlinks_pointing_outside() {
+    let sandbox = Sandbox::new("zip-link-out");
+    let archive = sandbox.archive("link.zip");
+    write_zip(
+        &archive,
+        &[
+            Item::Symlink("dir/up", "../../outside"),
+            Item::File("dir/up/evil.txt", b"bad"),
+        ],
+    );
+    assert_unsafe(extract_zip(
+        &archive,
+        &sandbox.out(),
+        &ExtractLimits::default(),
+    ));
+    assert!(!sandbox.root.join("outside").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn zip_rejects_writes_through_chained_symlinks() {
+    let sandbox = Sandbox::new("zip-link-chain");
+    let archive = sandbox.archive("chain.zip");
+    // 单独检查每个链接都在解压目录内，组合起来 c 指向解压目录的上一级
+    write_zip(
+        &archive,
+        &[
+            Item::Symlink("b", "."),
+            Item::Symlink("c", "b/.."),
+            Item::File("c/evil.txt", b"bad"),
+        ],
+    );
+    assert_unsafe(extract_zip(
+        &archive,
+        &sandbox.out(),
+        &ExtractLimits::default(),
+    ));
+    assert!(!sandbox.root.join("evil.txt").exists());
+}
+
+#[cfg(unix)]
+#[test]
+fn zip_rejects_links_escaping_through_earlier_links() {
+    let sandbox = Sandbox::new("zip-link-resolve");
+    let archive = sandbox.archive("resolve.zip");
+    // esc 在字面上指向解压目录内，经过 x 后实际指向上一级
+    write_zip(
+        &archive,
+        &[
+            Item::File("readme.txt", b"readme"),
+            Item::Symlink("x", "."),
+            Item::Symlink("esc", "x/.."),
+        ],
+    );
+    assert_unsafe(extract_zip(
+        &archive,
+        &sandbox.out(),
+        &ExtractLimits::default(),
+    ));
+    assert!(sandbox.out().join("esc").symlink_metadata().is_err());
+}
+
+#[test]
+fn zip_rejects_archives_larger_than_the_limit() {
+    let sandbox = Sandbox::new("zip-size");
+    let archive = sandbox.archive("bomb.zip");
+    let zeros = vec![0u8; 1024 * 1024];
+    write_zip(&archive, &[Item::File("zeros.bin", &zeros)]);
+    // 1MB 的 0 压缩后只有约 1KB
+    assert!(fs::metadata(&archive).unwrap().len() < 16 * 1024);
+    let limits = ExtractLimits {
+        max_size: 64 * 1024,
+        ..Default::default()
+    };
+    assert_unsafe(extract_zip(&archive, &sandbox.out(), &limits));
+    let written = fs::metadata(sandbox.out().join("zeros.bin")).map_or(0, |m| m.len());
+    assert!(written <= limits.max_size + 1);
+}
+
+#[test]
+fn zip_rejects_too_many_entries() {
+    let sandbox = Sandbox::new("zip-entries");
+    let archive = sandbox.archive("entries.zip");
+    let names: Vec<String> = (0..20).map(|i| format!("f{}.txt", i)).collect();
+    let items: Vec<Item> = names.iter().map(|n| Item::File(n, b"x")).collect();
+    write_zip(&archive, &items);
+    let limits = ExtractLimits {
+        max_entries: 10,
+        ..Default::default()
+    };
+    assert_unsafe(extract_zip(&archive, &sandbox.out(), &limits));
+}
+
+#[test]
+fn tpz_rejects_version_txt_escaping_the_templates_root() {
+    let sandbox = Sandbox::new("tpz-version");
+    let file = "Godot_v4.4.1-stable_export_templates.tpz";
+    let archive = sandbox.archive(file);
+    // version.txt 的内容会作为安装目录名
+    for version in [
+        "../../..",
+        "/tmp/evil",
+        "C:\\evil",
+        "4.4.1.stable/../../evil",
+        "a/b",
+    ] {
+        write_zip(
+            &archive,
+            &[
+                Item::File("templates/version.txt", version.as_bytes()),
+                Item::File("templates/linux_release.x86_64", b"bin"),
+            ],
+        );
+        assert_unsafe(get_tpz_template_dir(&archive, file));
+    }
+    write_zip(
+        &archive,
+        &[Item::File("templates/version.txt", b"4.4.1.stable\n")],
+    );
+    assert_eq!(
+        get_tpz_template_dir(&archive, file).unwrap(),
+        "4.4.1.stable"
+    );
+}
+
+#[test]
+fn tar_gz_extracts_regular_entries() {
+    let sandbox = Sandbox::new("tar-ok");
+    let archive = sandbox.archive("ok.tar.gz");
+    write_tar_gz(&archive, &[Item::File("config/projects.cfg", b"cfg")]);
+    let report = extract_tar_gz(&archive, &sandbox.out(), &ExtractLimits::default()).unwrap();
+    assert_eq!(report.extracted, 1);
+    assert_eq!(
+        fs::read(sandbox.out().join("config/projects.cfg")).unwrap(),
+        b"cfg"
+    );
+}
+
+#[test]
+fn tar_gz_rejects_parent_components() {
+    let sandbox = Sandbox::new("tar-parent");
+    let archive = sandbox.archive("parent.tar.gz");
+    write_tar_gz(&archive, &[Item::File("../evil.txt", b"bad")]);
+    assert_unsafe(extract_tar_gz(
+        &archive,
+        &sandbox.out(),
+        &ExtractLimits::default(),
+    ));
+    assert!(!sandbox.root.join("evil.txt").exists());
+}
+
+#[test]
+fn tar_gz_rejects_symlinks_pointing_outside() {
+    let sandbox = Sandbox::new("tar-link-out");
+    let archive = sandbox.archive("link.tar.gz");
+    write_tar_gz(
+        &archive,
+        &[
+            Item::Symlink("up", "../outside"),
+            Item::File("up/evil.txt", b"bad"),
+        ],
+    );
+    assert_unsafe(extract_tar_gz(
+        &archive,
+        &sandbox.out(),
+        &ExtractLimits::default(),
+    ));
+    assert!(!sandbox.root.join("outside").exists());
+}
+
+#[test]
+fn tar_gz_rejects_archives_larger_than_the_limit() {
+    let sandbox = Sandbox::new("tar-size");
+    let archive = sandbox.archive("bomb.tar.gz");
+    let zeros = vec![0u8; 1024 * 1024];
+    write_tar_gz(&archive, &[Item::File("zeros.bin", &zeros)]);
+    let limits = ExtractLimits {
+        max_size: 64 * 1024,
+        ..Default::default()
+    };
+    assert_unsafe(extract_tar_gz(&archive, &sandbox.out(), &limits));
+    assert!(!sandbox.out().join("zeros.bin").exists());
+}
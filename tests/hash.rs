@@ -0,0 +1,77 @@
+//! 将可保存状态的 SHA-512 与 sha2 的实现比较，覆盖填充的边界和分段输入
+
+use gdem::core::hash::Sha512State;
+use sha2::{Digest, Sha512};
+
+fn expected(data: &[u8]) -> String {
+    format!("{:x}", Sha512::digest(data))
+}
+
+/// 长度不同、内容不重复的数据
+fn sample(len: usize) -> Vec<u8> {
+    (0..len).map(|i| (i * 31 + i / 7) as u8).collect()
+}
+
+#[test]
+fn empty_input() {
+    assert_eq!(Sha512State::default().finish(), expected(b""));
+    assert_eq!(Sha512State::digest(b""), expected(b""));
+}
+
+#[test]
+fn padding_boundaries() {
+    // 111 字节时填充和长度字段正好放进一个块，112 字节起需要额外的块
+    for len in [1, 111, 112, 113, 127, 128, 129, 239, 240, 256] {
+        let data = sample(len);
+        assert_eq!(
+            Sha512State::digest(&data),
+            expected(&data),
+            "length {}",
+            len
+        );
+    }
+}
+
+#[test]
+fn multi_block_input() {
+    let data = sample(10 * 128 + 77);
+    assert_eq!(Sha512State::digest(&data), expected(&data));
+}
+
+#[test]
+fn split_updates() {
+    let data = sample(5 * 128 + 3);
+    for splits in [
+        &[1, 126, 1][..],
+        &[3, 128, 127, 129],
+        &[111, 1, 16],
+        &[0, 7, 0, 300],
+    ] {
+        let mut sha = Sha512State::default();
+        let mut pos = 0;
+        for &size in splits {
+            sha.update(&data[pos..pos + size]);
+            pos += size;
+        }
+        sha.update(&data[pos..]);
+        assert_eq!(sha.len(), data.len() as u64);
+        assert_eq!(sha.finish(), expected(&data), "splits {:?}", splits);
+    }
+}
+
+#[test]
+fn saved_state_continues() {
+    let data = sample(3 * 128 + 50);
+    for cut in [0, 1, 77, 128, 129, 300, data.len()] {
+        let mut sha = Sha512State::default();
+        sha.update(&data[..cut]);
+        // 与下载中断时相同，将中间状态写入文件后恢复
+        let json = serde_json::to_string(&sha).unwrap();
+        let mut sha: Sha512State = serde_json::from_str(&json).unwrap();
+        sha.update(&data[cut..cut + (data.len() - cut) / 3]);
+        let json = serde_json::to_string(&sha).unwrap();
+        let mut sha: Sha512State = serde_json::from_str(&json).unwrap();
+        sha.update(&data[cut + (data.len() - cut) / 3..]);
+        assert_eq!(sha.finish(), expected(&data), "cut at {}", cut);
+    }
+}
@@ -0,0 +1,71 @@
+//! 测试转移 Godot 数据目录时的复制和链接，符号链接按链接复制，有数据的目录不会被链接替换
+#![cfg(unix)]
+
+use gdem::core::utils::symlink as link_dir;
+use gdem::func::config::copy_dir_recursively;
+use std::fs;
+use std::os::unix::fs::symlink;
+use std::path::PathBuf;
+
+fn sandbox(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("gdem-relocate-{}-{}", std::process::id(), name));
+    if root.exists() {
+        fs::remove_dir_all(&root).unwrap();
+    }
+    fs::create_dir_all(&root).unwrap();
+    root
+}
+
+#[test]
+fn copy_keeps_symlinks_as_links() {
+    let root = sandbox("links");
+    let src = root.join("godot");
+    fs::create_dir_all(src.join("projects/demo")).unwrap();
+    fs::write(src.join("editor_settings-4.tres"), b"settings").unwrap();
+    fs::write(src.join("projects/demo/state.cfg"), b"state").unwrap();
+    let outside = root.join("shared");
+    fs::create_dir_all(&outside).unwrap();
+    fs::write(outside.join("template.gd"), b"extends Node").unwrap();
+    // 指向目录内的文件、目录外的目录，以及不存在的位置
+    symlink("editor_settings-4.tres", src.join("settings.tres")).unwrap();
+    symlink(&outside, src.join("script_templates")).unwrap();
+    symlink("missing", src.join("dangling")).unwrap();
+
+    let dst = root.join("data/config/godot");
+    copy_dir_recursively(&src, &dst).unwrap();
+
+    assert_eq!(
+        fs::read(dst.join("projects/demo/state.cfg")).unwrap(),
+        b"state"
+    );
+    for (name, target) in [
+        ("settings.tres", PathBuf::from("editor_settings-4.tres")),
+        ("script_templates", outside.clone()),
+        ("dangling", PathBuf::from("missing")),
+    ] {
+        let link = dst.join(name);
+        assert!(link.symlink_metadata().unwrap().is_symlink(), "{}", name);
+        assert_eq!(fs::read_link(&link).unwrap(), target, "{}", name);
+    }
+    fs::remove_dir_all(&root).unwrap();
+}
+
+#[test]
+fn symlink_never_replaces_a_populated_directory() {
+    let root = sandbox("replace");
+    let managed = root.join("data/Godot");
+    fs::create_dir_all(&managed).unwrap();
+    let system = root.join("godot");
+    fs::create_dir_all(&system).unwrap();
+    fs::write(system.join("projects.cfg"), b"projects").unwrap();
+
+    assert!(link_dir(&managed, &system).is_err());
+    assert_eq!(fs::read(system.join("projects.cfg")).unwrap(), b"projects");
+
+    // 空目录和已有的链接可以替换
+    fs::remove_file(system.join("projects.cfg")).unwrap();
+    link_dir(&managed, &system).unwrap();
+    link_dir(&root, &system).unwrap();
+    assert_eq!(fs::read_link(&system).unwrap(), root);
+    fs::remove_dir_all(&root).unwrap();
+}
@@ -0,0 +1,92 @@
+//! 测试恢复备份时拒绝会替换整个数据目录的条目，以及失败时的清理
+
+use flate2::Compression;
+use flate2::write::GzEncoder;
+use gdem::core::error::GdemError;
+use gdem::func::data::{preview_restore, restore_data};
+use std::fs::{self, File};
+use std::path::{Path, PathBuf};
+use tar::{Builder, EntryType, Header};
+
+fn sandbox(name: &str) -> PathBuf {
+    let root = std::env::temp_dir().join(format!("gdem-restore-{}-{}", std::process::id(), name));
+    if root.exists() {
+        fs::remove_dir_all(&root).unwrap();
+    }
+    fs::create_dir_all(&root).unwrap();
+    root
+}
+
+/// 直接写入头部的名称字段，`Header::set_path` 会整理路径中的 `.`
+///
+/// `links` 中的条目为 (名称, 链接目标) 的符号链接。
+fn write_backup(path: &Path, names: &[&str], links: &[(&str, &str)]) {
+    let encoder = GzEncoder::new(File::create(path).unwrap(), Compression::default());
+    let mut builder = Builder::new(encoder);
+    for name in names {
+        let mut header = Header::new_gnu();
+        header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+        header.set_entry_type(EntryType::Regular);
+        header.set_size(1);
+        header.set_mode(0o644);
+        header.set_cksum();
+        builder.append(&header, &b"x"[..]).unwrap();
+    }
+    for (name, target) in links {
+        let mut header = Header::new_gnu();
+        header.as_old_mut().name[..name.len()].copy_from_slice(name.as_bytes());
+        header.as_old_mut().linkname[..target.len()].copy_from_slice(target.as_bytes());
+        header.set_entry_type(EntryType::Symlink);
+        header.set_size(0);
+        header.set_mode(0o777);
+        header.set_cksum();
+        builder.append(&header, &[][..]).unwrap();
+    }
+    builder.into_inner().unwrap().finish().unwrap();
+}
+
+#[test]
+fn restore_rejects_entries_without_a_data_item() {
+    for (i, entry) in ["config/./projects.cfg", "config//projects.cfg", "config/"]
+        .iter()
+        .enumerate()
+    {
+        let root = sandbox(&i.to_string());
+        let archive = root.join("backup.tar.gz");
+        write_backup(&archive, &["config/projects.cfg", entry], &[]);
+        let data = root.join("data");
+        match preview_restore(&archive, &data) {
+            Err(GdemError::UnsafeArchive(_)) => {}
+            other => panic!(
+                "{}: expected an unsafe archive error, got {:?}",
+                entry, other
+            ),
+        }
+        assert!(matches!(
+            restore_data(&archive, &data),
+            Err(GdemError::UnsafeArchive(_))
+        ));
+        assert!(!data.join(".restore").exists());
+        fs::remove_dir_all(&root).unwrap();
+    }
+}
+
+#[test]
+fn restore_removes_the_staging_directory_when_extraction_fails() {
+    let root = sandbox("staging");
+    let archive = root.join("backup.tar.gz");
+    // 条目名称合法，解压时链接指向数据目录外而失败
+    write_backup(
+        &archive,
+        &["config/projects.cfg"],
+        &[("config/script_templates", "../../../outside")],
+    );
+    let data = root.join("data");
+    assert_eq!(preview_restore(&archive, &data).unwrap().len(), 2);
+    assert!(matches!(
+        restore_data(&archive, &data),
+        Err(GdemError::UnsafeArchive(_))
+    ));
+    assert!(!data.join(".restore").exists());
+    fs::remove_dir_all(&root).unwrap();
+}